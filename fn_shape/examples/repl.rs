@@ -0,0 +1,33 @@
+//! Explore a few functions interactively: `cargo run --example repl --features repl`
+//!
+//! Try `help`, `add 2 3`, `repeat "hi " times=3`, or `ad` followed by tab and enter to list
+//! its completions.
+
+use std::io;
+
+use fn_shape::fn_shape;
+use fn_shape::repl::Repl;
+
+/// Adds two numbers.
+#[fn_shape::facet_fn]
+fn add(x: i64, y: i64) -> i64 {
+    x + y
+}
+
+/// Repeats `text`, once unless `times` is given.
+#[fn_shape::facet_fn]
+fn repeat(text: &str, times: Option<usize>) -> String {
+    text.repeat(times.unwrap_or(1))
+}
+
+/// Parses an IP address.
+#[fn_shape::facet_fn]
+fn parse_ip(ip: String) -> Result<std::net::IpAddr, std::net::AddrParseError> {
+    ip.parse()
+}
+
+fn main() -> io::Result<()> {
+    let repl = Repl::new(vec![fn_shape!(add), fn_shape!(repeat), fn_shape!(parse_ip)]);
+    println!("Functions: `help` to list them, `quit` to leave");
+    repl.run(io::stdin().lock(), io::stdout())
+}
@@ -0,0 +1,516 @@
+//! A command-line interface over shaped functions.
+//!
+//! Each function is a subcommand named by its qualified name, e.g. `myapp add 2 3` or
+//! `myapp add --x 2 --y=3`. Arguments are decoded like those of
+//! [`FunctionShape::call_json`]: string parameters take the text as is and everything else
+//! is read as JSON, so `3`, `true` and `[1,2]` work. `Option` parameters and those with a
+//! default may be left out, and a `bool` flag is `true` without a value and `false` when
+//! left out. Help comes from the doc comments and the signature.
+
+use std::fmt;
+use std::process::ExitCode;
+
+use facet_value::{VObject, Value};
+
+use crate::json_schema::{JsonKind, split_doc};
+use crate::signature::tidy;
+use crate::{FunctionShape, JsonCallError, ParamShape};
+
+/// Why a command line could not be run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliError {
+    /// No function has this name
+    UnknownCommand(String),
+    /// `--flag` is not a parameter of `command`
+    UnknownFlag { command: String, flag: String },
+    /// `--flag` was last and takes a value
+    MissingValue { flag: String },
+    /// Parameter `name` was given twice
+    DuplicateArg { name: &'static str },
+    /// A positional argument beyond the last parameter
+    UnexpectedArg(String),
+    /// Decoding the arguments or the call failed
+    Call(JsonCallError),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::UnknownCommand(command) => write!(f, "unknown command `{command}`"),
+            CliError::UnknownFlag { command, flag } => {
+                write!(f, "`{command}` has no argument `{flag}`")
+            }
+            CliError::MissingValue { flag } => write!(f, "`{flag}` needs a value"),
+            CliError::DuplicateArg { name } => write!(f, "argument `{name}` given twice"),
+            CliError::UnexpectedArg(arg) => write!(f, "unexpected argument `{arg}`"),
+            CliError::Call(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+impl From<JsonCallError> for CliError {
+    fn from(err: JsonCallError) -> Self {
+        CliError::Call(err)
+    }
+}
+
+/// Runs shaped functions as subcommands
+pub struct Cli {
+    name: String,
+    shapes: Vec<FunctionShape>,
+}
+
+impl Cli {
+    /// A CLI called `name` in help text, with a subcommand for each shape that can be
+    /// called with JSON arguments
+    pub fn new(name: impl Into<String>, mut shapes: Vec<FunctionShape>) -> Self {
+        shapes.retain(FunctionShape::is_json_callable);
+        Cli {
+            name: name.into(),
+            shapes,
+        }
+    }
+
+    /// A CLI over every registered function
+    #[cfg(feature = "registry")]
+    pub fn from_registry(name: impl Into<String>) -> Self {
+        Cli::new(name, crate::registry::all())
+    }
+
+    /// Run the command line `args`, without the program name, and return the text to print:
+    /// the result of the call or the requested help. String results are printed without
+    /// quotes, other results as JSON, and `()` as nothing.
+    pub fn run<I, S>(&self, args: I) -> Result<String, CliError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let args: Vec<String> = args.into_iter().map(Into::into).collect();
+        let Some((command, rest)) = args.split_first() else {
+            return Ok(self.help());
+        };
+        match command.as_str() {
+            "help" | "--help" | "-h" => {
+                return match rest.first() {
+                    Some(command) => self.command(command).map(|shape| self.command_help(shape)),
+                    None => Ok(self.help()),
+                };
+            }
+            _ => {}
+        }
+        let shape = self.command(command)?;
+        let Some(values) = parse_args(shape, command, rest)? else {
+            return Ok(self.command_help(shape));
+        };
+        let mut object = VObject::new();
+        // Missing required arguments are left for `call_json` to report
+        for (param, value) in shape.params.iter().zip(values) {
+            let value = match value {
+                Some(value) => value,
+                None if param.is_required() && is_bool(param) => Value::from(false),
+                None => continue,
+            };
+            object.insert(param.name, value);
+        }
+        let output = shape.call_json_value(object.into())?;
+
+        Ok(match output.as_string() {
+            _ if output.is_null() => String::new(),
+            Some(string) => string.as_str().to_string(),
+            None => facet_json::to_string(&output).expect("JSON values always serialize"),
+        })
+    }
+
+    /// Run the process arguments, printing the output or the error. Usage errors exit with
+    /// code 2 and failed calls with code 1.
+    pub fn main(&self) -> ExitCode {
+        match self.run(std::env::args().skip(1)) {
+            Ok(output) => {
+                if !output.is_empty() {
+                    println!("{output}");
+                }
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                eprintln!("error: {err}");
+                match err {
+                    CliError::Call(JsonCallError::Call(_) | JsonCallError::Encode(_)) => {
+                        ExitCode::FAILURE
+                    }
+                    _ => ExitCode::from(2),
+                }
+            }
+        }
+    }
+
+    /// The list of commands
+    pub fn help(&self) -> String {
+        let width = self
+            .shapes
+            .iter()
+            .map(|shape| shape.qualified_name().len())
+            .max()
+            .unwrap_or(0);
+        let mut help = format!("Usage: {} <command> [arguments]\n\nCommands:\n", self.name);
+        for shape in &self.shapes {
+            let (description, _) = split_doc(shape.doc);
+            let summary = description.lines().next().unwrap_or("");
+            let line = format!("  {:width$}  {summary}", shape.qualified_name());
+            help.push_str(line.trim_end());
+            help.push('\n');
+        }
+        help.push_str(&format!(
+            "\nRun `{} help <command>` for the arguments of a command.",
+            self.name
+        ));
+        help
+    }
+
+    /// Usage, description and arguments of one command
+    pub fn command_help(&self, shape: &FunctionShape) -> String {
+        let (description, param_docs) = split_doc(shape.doc);
+        let mut help = String::new();
+        if !description.is_empty() {
+            help.push_str(&description);
+            help.push_str("\n\n");
+        }
+
+        help.push_str(&format!("Usage: {} {}", self.name, shape.qualified_name()));
+        for param in shape.params {
+            if !param.is_required() || is_bool(param) {
+                help.push_str(&format!(" [{}]", param.name));
+            } else {
+                help.push_str(&format!(" <{}>", param.name));
+            }
+        }
+        help.push('\n');
+
+        if !shape.params.is_empty() {
+            let flags: Vec<_> = shape
+                .params
+                .iter()
+                .map(|param| format!("--{} <{}>", param.name, tidy(param.type_name)))
+                .collect();
+            let width = flags.iter().map(String::len).max().unwrap_or(0);
+            help.push_str("\nArguments:\n");
+            for (param, flag) in shape.params.iter().zip(flags) {
+                let mut doc = param_docs
+                    .iter()
+                    .find(|(name, _)| *name == param.ident)
+                    .map_or(String::new(), |(_, doc)| doc.clone());
+                if let Some(default) = param.default {
+                    doc = format!("{doc} (default: {default})")
+                        .trim_start()
+                        .to_string();
+                }
+                let line = format!("  {flag:width$}  {doc}");
+                help.push_str(line.trim_end());
+                help.push('\n');
+            }
+        }
+        if !shape.ret.is_unit {
+            help.push_str(&format!("\nReturns: {}\n", tidy(shape.ret.type_name)));
+        }
+        help.trim_end().to_string()
+    }
+
+    fn command(&self, command: &str) -> Result<&FunctionShape, CliError> {
+        self.shapes
+            .iter()
+            .find(|shape| shape.qualified_name() == command)
+            .ok_or_else(|| CliError::UnknownCommand(command.to_string()))
+    }
+}
+
+/// One value per parameter, `None` where no argument was given, or `None` when help was
+/// asked for with `--help` or `-h` in place of a flag before `--`
+fn parse_args(
+    shape: &FunctionShape,
+    command: &str,
+    args: &[String],
+) -> Result<Option<Vec<Option<Value>>>, CliError> {
+    let mut values: Vec<Option<Value>> = vec![None; shape.params.len()];
+    let mut args = args.iter();
+    let mut flags_done = false;
+    while let Some(arg) = args.next() {
+        if !flags_done && arg == "-h" {
+            return Ok(None);
+        }
+        let flag = arg.strip_prefix("--").filter(|_| !flags_done);
+        let (param, text) = match flag {
+            Some("") => {
+                flags_done = true;
+                continue;
+            }
+            // A parameter called `help` takes the flag over
+            Some("help") if shape.params.iter().all(|param| param.name != "help") => {
+                return Ok(None);
+            }
+            Some(flag) => {
+                let (name, inline) = match flag.split_once('=') {
+                    Some((name, value)) => (name, Some(value.to_string())),
+                    None => (flag, None),
+                };
+                let name = name.replace('-', "_");
+                let param = shape
+                    .params
+                    .iter()
+                    .find(|param| param.name.replace('-', "_") == name)
+                    .ok_or_else(|| CliError::UnknownFlag {
+                        command: command.to_string(),
+                        flag: format!("--{}", flag.split('=').next().unwrap_or(flag)),
+                    })?;
+                let text = match inline {
+                    Some(text) => text,
+                    None if is_bool(param) => match args.as_slice().first().map(String::as_str) {
+                        Some(value @ ("true" | "false")) => {
+                            args.next();
+                            value.to_string()
+                        }
+                        _ => "true".to_string(),
+                    },
+                    None => args.next().cloned().ok_or_else(|| CliError::MissingValue {
+                        flag: arg.to_string(),
+                    })?,
+                };
+                (param, text)
+            }
+            None => {
+                let index = values
+                    .iter()
+                    .position(Option::is_none)
+                    .ok_or_else(|| CliError::UnexpectedArg(arg.to_string()))?;
+                (&shape.params[index], arg.to_string())
+            }
+        };
+        let slot = &mut values[param.index];
+        if slot.is_some() {
+            return Err(CliError::DuplicateArg { name: param.name });
+        }
+        *slot = Some(parse_value(param, &text));
+    }
+    Ok(Some(values))
+}
+
+/// `text` as a JSON string for string parameters, including `Option<String>`, otherwise as
+/// JSON if it parses
+fn parse_value(param: &ParamShape, text: &str) -> Value {
+    match kind(param) {
+        JsonKind::String => Value::from(text),
+        _ => facet_json::from_str::<Value>(text).unwrap_or_else(|_| Value::from(text)),
+    }
+}
+
+fn is_bool(param: &ParamShape) -> bool {
+    kind(param) == JsonKind::Boolean
+}
+
+fn kind(param: &ParamShape) -> JsonKind {
+    param.facet_shape.map_or(JsonKind::Any, JsonKind::of)
+}
+
+/// Run every registered function as a subcommand of this program
+#[cfg(feature = "registry")]
+pub fn main() -> ExitCode {
+    let name = std::env::args()
+        .next()
+        .as_deref()
+        .map(std::path::Path::new)
+        .and_then(|path| path.file_stem())
+        .map_or_else(
+            || "app".to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+    Cli::from_registry(name).main()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{facet_fn, fn_shape};
+
+    /// Adds two numbers.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - the first number
+    #[facet_fn(register = false)]
+    fn plus(x: i32, y: i32) -> i32 {
+        x + y
+    }
+
+    /// Greets someone.
+    #[facet_fn(register = false)]
+    fn greet(name: String, times: Option<u8>, loud: bool) -> String {
+        let greeting = format!("hello {name}").repeat(usize::from(times.unwrap_or(1)));
+        if loud {
+            greeting.to_uppercase()
+        } else {
+            greeting
+        }
+    }
+
+    #[facet_fn(register = false, rename_all = "kebab-case")]
+    fn sum(#[facet(default = 2)] max_len: usize, values: Vec<u32>) -> Vec<u32> {
+        values.into_iter().take(max_len).collect()
+    }
+
+    #[facet_fn(register = false)]
+    fn nothing() {}
+
+    #[facet_fn(register = false)]
+    fn label(title: Option<String>) -> String {
+        title.unwrap_or_default()
+    }
+
+    fn cli() -> Cli {
+        Cli::new(
+            "app",
+            vec![
+                fn_shape!(plus),
+                fn_shape!(greet),
+                fn_shape!(sum),
+                fn_shape!(nothing),
+                fn_shape!(label),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_run() {
+        let cli = cli();
+        assert_eq!(cli.run(["plus", "2", "3"]), Ok("5".to_string()));
+        assert_eq!(cli.run(["plus", "--y", "3", "--x=-2"]), Ok("1".to_string()));
+        assert_eq!(cli.run(["plus", "--y", "3", "2"]), Ok("5".to_string()));
+        assert_eq!(cli.run(["plus", "--", "-2", "3"]), Ok("1".to_string()));
+
+        // Strings are taken as is, `Option`s may be left out and bool flags need no value
+        assert_eq!(
+            cli.run(["greet", "42", "--loud"]),
+            Ok("HELLO 42".to_string())
+        );
+        assert_eq!(
+            cli.run(["greet", "--loud", "false", "bob", "2"]),
+            Ok("hello bobhello bob".to_string())
+        );
+        assert_eq!(
+            cli.run(["sum", "--max-len", "2", "[1,2,3]"]),
+            Ok("[1,2]".to_string())
+        );
+        assert_eq!(
+            cli.run(["sum", "--values", "[1,2,3]"]),
+            Ok("[1,2]".to_string())
+        );
+        assert_eq!(cli.run(["nothing"]), Ok(String::new()));
+        nothing();
+    }
+
+    #[test]
+    fn test_run_options_and_flags() {
+        let cli = cli();
+        // `Option<String>` takes the text as is, however it would read as JSON
+        assert_eq!(cli.run(["label", "true"]), Ok("true".to_string()));
+        assert_eq!(cli.run(["label", "--title", "[1]"]), Ok("[1]".to_string()));
+        assert_eq!(cli.run(["label"]), Ok(String::new()));
+
+        // A left out bool flag is `false`
+        assert_eq!(cli.run(["greet", "bob"]), Ok("hello bob".to_string()));
+
+        // Help flags are only help in place of a flag before `--`
+        assert_eq!(cli.run(["label", "--title", "-h"]), Ok("-h".to_string()));
+        assert_eq!(
+            cli.run(["label", "--title=--help"]),
+            Ok("--help".to_string())
+        );
+        assert_eq!(cli.run(["label", "--", "--help"]), Ok("--help".to_string()));
+        assert_eq!(cli.run(["label", "--", "-h"]), Ok("-h".to_string()));
+    }
+
+    #[test]
+    fn test_errors() {
+        let cli = cli();
+        assert_eq!(
+            cli.run(["minus"]),
+            Err(CliError::UnknownCommand("minus".to_string()))
+        );
+        assert_eq!(
+            cli.run(["plus", "--z", "1"]),
+            Err(CliError::UnknownFlag {
+                command: "plus".to_string(),
+                flag: "--z".to_string()
+            })
+        );
+        assert_eq!(
+            cli.run(["plus", "1", "--y"]),
+            Err(CliError::MissingValue {
+                flag: "--y".to_string()
+            })
+        );
+        assert_eq!(
+            cli.run(["plus", "--x", "1", "--x=2"]),
+            Err(CliError::DuplicateArg { name: "x" })
+        );
+        assert_eq!(
+            cli.run(["plus", "1", "2", "3"]),
+            Err(CliError::UnexpectedArg("3".to_string()))
+        );
+        assert_eq!(
+            cli.run(["plus", "1"]),
+            Err(CliError::Call(JsonCallError::MissingArg { name: "y" }))
+        );
+        assert!(matches!(
+            cli.run(["plus", "1", "two"]),
+            Err(CliError::Call(JsonCallError::ArgType { name: "y", .. }))
+        ));
+    }
+
+    #[test]
+    fn test_help() {
+        let cli = cli();
+        let help = cli.run(Vec::<String>::new()).expect("should show help");
+        assert_eq!(
+            help,
+            "Usage: app <command> [arguments]\n\n\
+             Commands:\n  \
+               plus     Adds two numbers.\n  \
+               greet    Greets someone.\n  \
+               sum\n  \
+               nothing\n  \
+               label\n\n\
+             Run `app help <command>` for the arguments of a command."
+        );
+        assert_eq!(cli.run(["--help"]), Ok(help));
+
+        let help = cli.run(["help", "plus"]).expect("should show help");
+        assert_eq!(
+            help,
+            "Adds two numbers.\n\n\
+             Usage: app plus <x> <y>\n\n\
+             Arguments:\n  \
+               --x <i32>  the first number\n  \
+               --y <i32>\n\n\
+             Returns: i32"
+        );
+        assert_eq!(cli.run(["plus", "1", "--help"]), Ok(help));
+        assert_eq!(
+            cli.run(["greet", "-h"])
+                .map(|help| help.lines().nth(2).map(str::to_string)),
+            Ok(Some("Usage: app greet <name> [times] [loud]".to_string()))
+        );
+        assert_eq!(
+            cli.run(["help", "sum"]),
+            Ok("Usage: app sum [max-len] <values>\n\n\
+                Arguments:\n  \
+                  --max-len <usize>    (default: 2)\n  \
+                  --values <Vec<u32>>\n\n\
+                Returns: Vec<u32>"
+                .to_string())
+        );
+        assert_eq!(
+            cli.run(["help", "minus"]),
+            Err(CliError::UnknownCommand("minus".to_string()))
+        );
+    }
+}
@@ -0,0 +1,109 @@
+//! Calling shaped functions with type-erased arguments.
+//!
+//! Arguments are boxed as the exact parameter types, so a `&str` parameter takes a boxed
+//! `&'static str`. A method's receiver comes first: `&self` and `&mut self` borrow a boxed
+//! `Self`, while `self` and `self: Box<Self>` move it out.
+
+use core::any::{Any, type_name};
+use core::fmt;
+
+/// The boxed return value of a dynamic call
+pub type DynResult = Result<Box<dyn Any>, CallError>;
+
+/// A type-erased entry point generated for a shaped function
+#[derive(Clone, Copy)]
+pub struct DynCall(pub fn(&mut [Box<dyn Any>]) -> DynResult);
+
+fn_pointer_impls!(DynCall);
+
+/// Produces the boxed default of a parameter with `#[facet(default)]`
+#[derive(Clone, Copy)]
+pub struct DefaultValue(pub fn() -> Box<dyn Any>);
+
+fn_pointer_impls!(DefaultValue);
+
+/// Why a dynamic call was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CallError {
+    /// The function cannot be called dynamically: it is generic, `async` or `unsafe`, or
+    /// takes or returns `impl Trait`
+    NotCallable { name: &'static str },
+    /// Wrong number of arguments, counting the receiver
+    ArgCount { expected: usize, got: usize },
+    /// The argument at `index` is not of the expected type
+    ArgType {
+        index: usize,
+        expected: &'static str,
+    },
+    /// The argument at `index` was already moved out by an earlier call
+    ArgMoved { index: usize },
+}
+
+impl fmt::Display for CallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CallError::NotCallable { name } => {
+                write!(f, "function `{name}` cannot be called dynamically")
+            }
+            CallError::ArgCount { expected, got } => {
+                write!(f, "expected {expected} arguments, got {got}")
+            }
+            CallError::ArgType { index, expected } => {
+                write!(f, "argument {index} should be of type `{expected}`")
+            }
+            CallError::ArgMoved { index } => write!(f, "argument {index} was already moved"),
+        }
+    }
+}
+
+impl std::error::Error for CallError {}
+
+/// Placeholder left behind by [`take`]
+struct Moved;
+
+#[doc(hidden)]
+pub fn check_count(args: &[Box<dyn Any>], expected: usize) -> Result<(), CallError> {
+    if args.len() == expected {
+        Ok(())
+    } else {
+        Err(CallError::ArgCount {
+            expected,
+            got: args.len(),
+        })
+    }
+}
+
+#[doc(hidden)]
+pub fn check<T: Any>(args: &[Box<dyn Any>], index: usize) -> Result<(), CallError> {
+    if args[index].is::<T>() {
+        Ok(())
+    } else if args[index].is::<Moved>() {
+        Err(CallError::ArgMoved { index })
+    } else {
+        Err(CallError::ArgType {
+            index,
+            expected: type_name::<T>(),
+        })
+    }
+}
+
+/// Move an argument out, leaving a placeholder; every argument is checked beforehand so that
+/// a failed call moves nothing
+#[doc(hidden)]
+pub fn take<T: Any>(args: &mut [Box<dyn Any>], index: usize) -> Result<T, CallError> {
+    check::<T>(args, index)?;
+    let arg = core::mem::replace(&mut args[index], Box::new(Moved));
+    Ok(*arg.downcast::<T>().expect("checked above"))
+}
+
+#[doc(hidden)]
+pub fn borrow<T: Any>(args: &[Box<dyn Any>], index: usize) -> Result<&T, CallError> {
+    check::<T>(args, index)?;
+    Ok(args[index].downcast_ref::<T>().expect("checked above"))
+}
+
+#[doc(hidden)]
+pub fn borrow_mut<T: Any>(args: &mut [Box<dyn Any>], index: usize) -> Result<&mut T, CallError> {
+    check::<T>(args, index)?;
+    Ok(args[index].downcast_mut::<T>().expect("checked above"))
+}
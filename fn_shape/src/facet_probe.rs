@@ -0,0 +1,24 @@
+//! Support code for the `facet_shape` fields emitted by the macros.
+//!
+//! `FacetProbe::<T>::SHAPE` is `Some(T::SHAPE)` when `T` implements `Facet` and `None`
+//! otherwise: the inherent constant only exists for `Facet` types, so name resolution falls
+//! back to the trait constant for every other type. The generated code brings [`NoFacet`]
+//! into scope for this.
+
+use core::marker::PhantomData;
+
+use facet::{Facet, Shape};
+
+/// Resolves to the facet shape of `T`, if it has one
+pub struct FacetProbe<T: ?Sized>(PhantomData<T>);
+
+impl<'facet, T: ?Sized + Facet<'facet>> FacetProbe<T> {
+    pub const SHAPE: Option<&'static Shape> = Some(T::SHAPE);
+}
+
+/// The fallback for types that do not implement `Facet`
+pub trait NoFacet {
+    const SHAPE: Option<&'static Shape> = None;
+}
+
+impl<T: ?Sized> NoFacet for FacetProbe<T> {}
@@ -0,0 +1,244 @@
+//! Calling shaped functions with JSON arguments.
+//!
+//! Arguments are given as an object keyed by parameter name or as a positional array, and
+//! each is decoded with facet into its parameter type before the call goes through
+//! [`FunctionShape::call_dyn`]. `Option` parameters and those with `#[facet(default)]` may
+//! be omitted. Only parameter and return types implementing `Facet` can be decoded and
+//! encoded, and of references only `&str`, whose text is leaked. Methods taking `self`
+//! cannot be called this way.
+
+use core::any::Any;
+use core::fmt;
+use core::marker::PhantomData;
+
+use facet::Facet;
+use facet_value::Value;
+
+use crate::{CallError, FunctionShape, ParamShape};
+
+/// Decodes a JSON value into a boxed parameter value
+#[derive(Clone, Copy)]
+pub struct JsonDecode(pub fn(Value) -> Result<Box<dyn Any>, String>);
+
+/// Encodes a boxed return value as a JSON value
+#[derive(Clone, Copy)]
+pub struct JsonEncode(pub fn(&dyn Any) -> Result<Value, String>);
+
+fn_pointer_impls!(JsonDecode);
+fn_pointer_impls!(JsonEncode);
+
+fn decode<T: Facet<'static>>(value: Value) -> Result<Box<dyn Any>, String> {
+    facet_value::from_value::<T>(value)
+        .map(|value| Box::new(value) as Box<dyn Any>)
+        .map_err(|err| err.to_string())
+}
+
+fn encode<T: Facet<'static>>(value: &dyn Any) -> Result<Value, String> {
+    let value = value
+        .downcast_ref::<T>()
+        .ok_or_else(|| format!("return value is not a `{}`", T::SHAPE))?;
+    facet_value::to_value(value).map_err(|err| err.to_string())
+}
+
+/// The decoder of `&str` parameters, which leaks the string to give it a `'static` lifetime
+#[doc(hidden)]
+pub fn decode_str(value: Value) -> Result<Box<dyn Any>, String> {
+    let text: String = facet_value::from_value(value).map_err(|err| err.to_string())?;
+    let text: &'static str = Box::leak(text.into_boxed_str());
+    Ok(Box::new(text))
+}
+
+/// Resolves to the JSON codec of `T`, like [`crate::facet_probe::FacetProbe`]
+#[doc(hidden)]
+pub struct JsonProbe<T: ?Sized>(PhantomData<T>);
+
+impl<T: Facet<'static>> JsonProbe<T> {
+    pub const DECODE: Option<JsonDecode> = Some(JsonDecode(decode::<T>));
+    pub const ENCODE: Option<JsonEncode> = Some(JsonEncode(encode::<T>));
+}
+
+/// The fallback for types that do not implement `Facet`
+#[doc(hidden)]
+pub trait NoJson {
+    const DECODE: Option<JsonDecode> = None;
+    const ENCODE: Option<JsonEncode> = None;
+}
+
+impl<T: ?Sized> NoJson for JsonProbe<T> {}
+
+/// Why a JSON call failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonCallError {
+    /// The function cannot be called dynamically, or takes `self`
+    NotCallable { function: &'static str },
+    /// The type of parameter `name` does not implement `Facet`
+    NotDecodable { name: &'static str },
+    /// The return type does not implement `Facet`
+    NotEncodable { type_name: &'static str },
+    /// The input is not valid JSON
+    InvalidJson(String),
+    /// The input is neither an object nor an array
+    NotArguments,
+    /// No value was given for parameter `name`
+    MissingArg { name: &'static str },
+    /// The object has a key that is not a parameter name
+    UnknownArg { name: String },
+    /// The array has more values than the function has parameters
+    TooManyArgs { expected: usize, got: usize },
+    /// The value for parameter `name` does not decode into its type
+    ArgType { name: &'static str, message: String },
+    /// The dynamic call itself failed
+    Call(CallError),
+    /// The return value could not be serialized
+    Encode(String),
+}
+
+impl fmt::Display for JsonCallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonCallError::NotCallable { function } => {
+                write!(
+                    f,
+                    "function `{function}` cannot be called with JSON arguments"
+                )
+            }
+            JsonCallError::NotDecodable { name } => {
+                write!(
+                    f,
+                    "the type of argument `{name}` cannot be decoded from JSON"
+                )
+            }
+            JsonCallError::NotEncodable { type_name } => {
+                write!(f, "return type `{type_name}` cannot be encoded as JSON")
+            }
+            JsonCallError::InvalidJson(message) => write!(f, "invalid JSON: {message}"),
+            JsonCallError::NotArguments => f.write_str("arguments must be a JSON object or array"),
+            JsonCallError::MissingArg { name } => write!(f, "missing argument `{name}`"),
+            JsonCallError::UnknownArg { name } => write!(f, "unknown argument `{name}`"),
+            JsonCallError::TooManyArgs { expected, got } => {
+                write!(f, "expected at most {expected} arguments, got {got}")
+            }
+            JsonCallError::ArgType { name, message } => {
+                write!(f, "invalid argument `{name}`: {message}")
+            }
+            JsonCallError::Call(err) => err.fmt(f),
+            JsonCallError::Encode(message) => {
+                write!(f, "failed to encode return value: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for JsonCallError {}
+
+impl From<CallError> for JsonCallError {
+    fn from(err: CallError) -> Self {
+        JsonCallError::Call(err)
+    }
+}
+
+impl FunctionShape {
+    /// Whether [`FunctionShape::call_json`] can call the function: it is callable
+    /// dynamically, takes no `self`, its parameters can be decoded and its return type
+    /// implements `Facet`
+    pub fn is_json_callable(&self) -> bool {
+        self.call.is_some()
+            && self.receiver.is_none()
+            && self.params.iter().all(|param| param.json_decode.is_some())
+            && self.ret.json_encode.is_some()
+    }
+
+    /// Call the function with JSON arguments and return its result as JSON, e.g.
+    /// `{"x": 1, "y": 2}` or `[1, 2]` for `add(x: i32, y: i32)`
+    pub fn call_json(&self, input: &str) -> Result<String, JsonCallError> {
+        let input: Value = facet_json::from_str(input)
+            .map_err(|err| JsonCallError::InvalidJson(err.to_string()))?;
+        let output = self.call_json_value(input)?;
+        facet_json::to_string(&output).map_err(|err| JsonCallError::Encode(err.to_string()))
+    }
+
+    /// [`FunctionShape::call_json`] on JSON values that are already parsed
+    pub fn call_json_value(&self, input: Value) -> Result<Value, JsonCallError> {
+        if self.call.is_none() || self.receiver.is_some() {
+            return Err(JsonCallError::NotCallable {
+                function: self.name,
+            });
+        }
+        let decoders = self
+            .params
+            .iter()
+            .map(|param| {
+                param
+                    .json_decode
+                    .ok_or(JsonCallError::NotDecodable { name: param.name })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let encode = self.ret.json_encode.ok_or(JsonCallError::NotEncodable {
+            type_name: self.ret.type_name,
+        })?;
+
+        let values = self.arg_values(input)?;
+
+        let mut args = self
+            .params
+            .iter()
+            .zip(decoders)
+            .zip(values)
+            .map(
+                |((param, decode), value)| match (value, param.default_value) {
+                    (Some(value), _) => {
+                        (decode.0)(value).map_err(|message| JsonCallError::ArgType {
+                            name: param.name,
+                            message,
+                        })
+                    }
+                    (None, Some(default)) => Ok((default.0)()),
+                    (None, None) => Err(JsonCallError::MissingArg { name: param.name }),
+                },
+            )
+            .collect::<Result<Vec<_>, _>>()?;
+        let ret = self.call_dyn(&mut args)?;
+        (encode.0)(&*ret).map_err(JsonCallError::Encode)
+    }
+
+    /// One JSON value per parameter, in parameter order; `None` for omitted parameters that
+    /// are not required
+    fn arg_values(&self, input: Value) -> Result<Vec<Option<Value>>, JsonCallError> {
+        if let Some(object) = input.as_object() {
+            if let Some((key, _)) = object
+                .iter()
+                .find(|(key, _)| !self.param_names.contains(&key.as_str()))
+            {
+                return Err(JsonCallError::UnknownArg {
+                    name: key.as_str().to_string(),
+                });
+            }
+            self.params
+                .iter()
+                .map(|param| given(param, object.get(param.name)))
+                .collect()
+        } else if let Some(array) = input.as_array() {
+            if array.len() > self.params.len() {
+                return Err(JsonCallError::TooManyArgs {
+                    expected: self.params.len(),
+                    got: array.len(),
+                });
+            }
+            self.params
+                .iter()
+                .map(|param| given(param, array.get(param.index)))
+                .collect()
+        } else {
+            Err(JsonCallError::NotArguments)
+        }
+    }
+}
+
+/// The value given for `param`, which may only be missing when the parameter is not required
+fn given(param: &ParamShape, value: Option<&Value>) -> Result<Option<Value>, JsonCallError> {
+    match value {
+        Some(value) => Ok(Some(value.clone())),
+        None if !param.is_required() => Ok(None),
+        None => Err(JsonCallError::MissingArg { name: param.name }),
+    }
+}
@@ -0,0 +1,431 @@
+//! JSON Schema export for tool-calling APIs.
+//!
+//! Property types are derived from the facet shapes of the parameters, so parameters whose
+//! types do not implement `Facet` accept any value. Descriptions come from the doc comment:
+//! the text outside an `# Arguments` section describes the function, and bullets in that
+//! section such as ``* `x` - the first number`` describe the parameters.
+
+use facet::{Def, ScalarType, Shape, StructKind, Type, UserType};
+use facet_value::{VArray, VObject, Value};
+
+use crate::FunctionShape;
+
+/// How deep nested types are described before falling back to accepting any value, which
+/// keeps recursive types finite
+const MAX_DEPTH: usize = 16;
+
+impl FunctionShape {
+    /// A tool definition of the form `{"name", "description", "parameters"}`, where
+    /// `parameters` is an object schema with one property per parameter. `Option` parameters
+    /// and those with a default are not required.
+    pub fn json_schema(&self) -> String {
+        facet_json::to_string(&self.json_schema_value()).expect("JSON values always serialize")
+    }
+
+    /// [`FunctionShape::json_schema`] as a JSON value
+    pub fn json_schema_value(&self) -> Value {
+        let (description, param_docs) = split_doc(self.doc);
+
+        let mut properties = VObject::new();
+        let mut required = VArray::new();
+        for param in self.params {
+            let mut schema = match param.facet_shape {
+                Some(shape) => type_schema(shape),
+                None => VObject::new(),
+            };
+            if let Some((_, doc)) = param_docs.iter().find(|(name, _)| *name == param.ident) {
+                schema.insert("description", doc.as_str());
+            }
+            properties.insert(param.name, schema);
+            if param.is_required() {
+                required.push(Value::from(param.name));
+            }
+        }
+
+        let mut parameters = VObject::new();
+        parameters.insert("type", "object");
+        parameters.insert("properties", properties);
+        parameters.insert("required", required);
+
+        let mut tool = VObject::new();
+        tool.insert("name", self.name);
+        tool.insert("description", description);
+        tool.insert("parameters", parameters);
+        tool.into()
+    }
+}
+
+/// The function description and the `(name, description)` bullets of its `# Arguments`
+/// section
+pub(crate) fn split_doc(doc: &str) -> (String, Vec<(&str, String)>) {
+    let mut description = Vec::new();
+    let mut params: Vec<(&str, String)> = Vec::new();
+    let mut in_arguments = false;
+    for line in doc.lines() {
+        if let Some(heading) = line.strip_prefix('#') {
+            let heading = heading.trim_start_matches('#').trim();
+            in_arguments = matches!(heading, "Arguments" | "Parameters");
+            if in_arguments {
+                continue;
+            }
+        }
+        if !in_arguments {
+            description.push(line);
+            continue;
+        }
+        let bullet = line
+            .trim_start()
+            .strip_prefix("* ")
+            .or_else(|| line.trim_start().strip_prefix("- "));
+        match bullet {
+            Some(bullet) => {
+                let bullet = bullet.trim_start_matches('`');
+                let name_end = bullet
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .unwrap_or(bullet.len());
+                let (name, rest) = bullet.split_at(name_end);
+                let text = rest
+                    .trim_start_matches('`')
+                    .trim_start()
+                    .trim_start_matches(['-', ':'])
+                    .trim();
+                params.push((name, text.to_string()));
+            }
+            // Indented lines continue the previous bullet
+            None if line.starts_with(' ') => {
+                if let Some((_, text)) = params.last_mut() {
+                    text.push(' ');
+                    text.push_str(line.trim());
+                }
+            }
+            None => {}
+        }
+    }
+    (description.join("\n").trim().to_string(), params)
+}
+
+/// The JSON type values of a shape are written as
+#[cfg(feature = "cli")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum JsonKind {
+    Null,
+    Boolean,
+    Integer,
+    Number,
+    String,
+    Array,
+    Object,
+    /// More than one type, or a type that cannot be described
+    Any,
+}
+
+#[cfg(feature = "cli")]
+impl JsonKind {
+    /// The `type` of the [`type_schema`] of `shape`, where an `Option` takes the kind of the
+    /// value it holds
+    pub(crate) fn of(shape: &'static Shape) -> JsonKind {
+        if let Def::Option(option) = shape.def {
+            return JsonKind::of(option.t);
+        }
+        let schema = type_schema(shape);
+        match schema
+            .get("type")
+            .and_then(Value::as_string)
+            .map(|ty| ty.as_str())
+        {
+            Some("null") => JsonKind::Null,
+            Some("boolean") => JsonKind::Boolean,
+            Some("integer") => JsonKind::Integer,
+            Some("number") => JsonKind::Number,
+            Some("string") => JsonKind::String,
+            Some("array") => JsonKind::Array,
+            Some("object") => JsonKind::Object,
+            _ => JsonKind::Any,
+        }
+    }
+}
+
+/// The schema for values of `shape`; types that cannot be described accept any value
+pub(crate) fn type_schema(shape: &'static Shape) -> VObject {
+    schema_at(shape, 0)
+}
+
+fn schema_at(shape: &'static Shape, depth: usize) -> VObject {
+    let mut schema = VObject::new();
+    if depth > MAX_DEPTH {
+        return schema;
+    }
+    let nested = |shape| Value::from(schema_at(shape, depth + 1));
+
+    if let Some(scalar) = shape.scalar_type() {
+        match scalar {
+            ScalarType::Unit => {
+                schema.insert("type", "null");
+            }
+            ScalarType::Bool => {
+                schema.insert("type", "boolean");
+            }
+            ScalarType::F32 | ScalarType::F64 => {
+                schema.insert("type", "number");
+            }
+            ScalarType::U8
+            | ScalarType::U16
+            | ScalarType::U32
+            | ScalarType::U64
+            | ScalarType::U128
+            | ScalarType::USize => {
+                schema.insert("type", "integer");
+                schema.insert("minimum", 0);
+            }
+            ScalarType::I8
+            | ScalarType::I16
+            | ScalarType::I32
+            | ScalarType::I64
+            | ScalarType::I128
+            | ScalarType::ISize => {
+                schema.insert("type", "integer");
+            }
+            ScalarType::Char => {
+                schema.insert("type", "string");
+                schema.insert("minLength", 1);
+                schema.insert("maxLength", 1);
+            }
+            // Strings, addresses and the like are all written as strings
+            _ => {
+                schema.insert("type", "string");
+            }
+        }
+        return schema;
+    }
+
+    match shape.def {
+        Def::Option(option) => {
+            let mut null = VObject::new();
+            null.insert("type", "null");
+            let variants: VArray = [nested(option.t), null.into()].into_iter().collect();
+            schema.insert("anyOf", variants);
+            return schema;
+        }
+        Def::List(list) => {
+            schema.insert("type", "array");
+            schema.insert("items", nested(list.t));
+            return schema;
+        }
+        Def::Slice(slice) => {
+            schema.insert("type", "array");
+            schema.insert("items", nested(slice.t));
+            return schema;
+        }
+        Def::Set(set) => {
+            schema.insert("type", "array");
+            schema.insert("items", nested(set.t));
+            schema.insert("uniqueItems", true);
+            return schema;
+        }
+        Def::Array(array) => {
+            schema.insert("type", "array");
+            schema.insert("items", nested(array.t));
+            schema.insert("minItems", array.n);
+            schema.insert("maxItems", array.n);
+            return schema;
+        }
+        Def::Map(map) => {
+            schema.insert("type", "object");
+            schema.insert("additionalProperties", nested(map.v));
+            return schema;
+        }
+        Def::Pointer(pointer) => {
+            return match pointer.pointee() {
+                Some(pointee) => schema_at(pointee, depth),
+                None => schema,
+            };
+        }
+        // Other scalars, such as paths, are written as strings
+        Def::Scalar => {
+            schema.insert("type", "string");
+            return schema;
+        }
+        _ => {}
+    }
+
+    match shape.ty {
+        Type::Pointer(facet::PointerType::Reference(pointer)) => schema_at(pointer.target, depth),
+        Type::User(UserType::Struct(user)) => match user.kind {
+            StructKind::Unit => {
+                schema.insert("type", "null");
+                schema
+            }
+            StructKind::Tuple | StructKind::TupleStruct => {
+                let items: VArray = user.fields.iter().map(|f| nested(f.shape())).collect();
+                schema.insert("type", "array");
+                schema.insert("prefixItems", items);
+                schema.insert("minItems", user.fields.len());
+                schema.insert("maxItems", user.fields.len());
+                schema
+            }
+            StructKind::Struct => {
+                let mut properties = VObject::new();
+                let mut required = VArray::new();
+                for field in user.fields {
+                    properties.insert(field.effective_name(), nested(field.shape()));
+                    let optional = matches!(field.shape().def, Def::Option(_));
+                    if !optional && !field.has_default() {
+                        required.push(Value::from(field.effective_name()));
+                    }
+                }
+                schema.insert("type", "object");
+                schema.insert("properties", properties);
+                schema.insert("required", required);
+                schema
+            }
+        },
+        // Enums of unit variants are written as the variant name
+        Type::User(UserType::Enum(user))
+            if user
+                .variants
+                .iter()
+                .all(|variant| variant.data.kind == StructKind::Unit) =>
+        {
+            let names: VArray = user
+                .variants
+                .iter()
+                .map(|variant| Value::from(variant.effective_name()))
+                .collect();
+            schema.insert("type", "string");
+            schema.insert("enum", names);
+            schema
+        }
+        _ => match shape.inner {
+            // Transparent wrappers are written as the type they wrap
+            Some(inner) => schema_at(inner, depth),
+            None => schema,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashMap};
+
+    use facet::Facet;
+
+    use super::*;
+    use crate::{facet_fn, fn_shape};
+
+    /// Finds a user.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - the user's login
+    /// * `limit` - at most this many results
+    #[facet_fn]
+    fn find_user(name: &str, limit: Option<u8>, #[facet(default)] tags: Vec<String>) -> usize {
+        name.len() + usize::from(limit.unwrap_or(0)) + tags.len()
+    }
+
+    #[derive(Facet)]
+    struct Point {
+        x: i32,
+        #[facet(rename = "why")]
+        y: Option<i32>,
+    }
+
+    #[derive(Facet)]
+    #[repr(u8)]
+    #[allow(dead_code)]
+    enum Color {
+        Red,
+        Green,
+    }
+
+    fn schema_of<'a, T: Facet<'a>>() -> String {
+        facet_json::to_string(&Value::from(type_schema(T::SHAPE))).expect("should serialize")
+    }
+
+    #[test]
+    fn test_json_schema() {
+        assert_eq!(
+            fn_shape!(find_user).json_schema(),
+            concat!(
+                r#"{"name":"find_user","description":"Finds a user.","parameters":{"type":"object","properties":{"#,
+                r#""name":{"type":"string","description":"the user's login"},"#,
+                r#""limit":{"anyOf":[{"type":"integer","minimum":0},{"type":"null"}],"description":"at most this many results"},"#,
+                r#""tags":{"type":"array","items":{"type":"string"}}},"#,
+                r#""required":["name"]}}"#
+            )
+        );
+        assert_eq!(find_user("a", None, vec![]), 1);
+    }
+
+    #[test]
+    fn test_type_schemas() {
+        assert_eq!(schema_of::<i32>(), r#"{"type":"integer"}"#);
+        assert_eq!(schema_of::<&str>(), r#"{"type":"string"}"#);
+        assert_eq!(schema_of::<std::path::PathBuf>(), r#"{"type":"string"}"#);
+        assert_eq!(
+            schema_of::<&mut Vec<String>>(),
+            r#"{"type":"array","items":{"type":"string"}}"#
+        );
+        assert_eq!(
+            schema_of::<[u8; 4]>(),
+            r#"{"type":"array","items":{"type":"integer","minimum":0},"minItems":4,"maxItems":4}"#
+        );
+        assert_eq!(
+            schema_of::<(bool, f64)>(),
+            r#"{"type":"array","prefixItems":[{"type":"boolean"},{"type":"number"}],"minItems":2,"maxItems":2}"#
+        );
+        assert_eq!(
+            schema_of::<HashMap<String, Option<i64>>>(),
+            r#"{"type":"object","additionalProperties":{"anyOf":[{"type":"integer"},{"type":"null"}]}}"#
+        );
+        assert_eq!(
+            schema_of::<BTreeSet<char>>(),
+            r#"{"type":"array","items":{"type":"string","minLength":1,"maxLength":1},"uniqueItems":true}"#
+        );
+        assert_eq!(schema_of::<Box<()>>(), r#"{"type":"null"}"#);
+    }
+
+    #[test]
+    fn test_user_type_schemas() {
+        assert_eq!(
+            schema_of::<Point>(),
+            concat!(
+                r#"{"type":"object","properties":{"x":{"type":"integer"},"#,
+                r#""why":{"anyOf":[{"type":"integer"},{"type":"null"}]}},"required":["x"]}"#
+            )
+        );
+        assert_eq!(
+            schema_of::<Color>(),
+            r#"{"type":"string","enum":["Red","Green"]}"#
+        );
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_json_kinds() {
+        assert_eq!(JsonKind::of(<&str>::SHAPE), JsonKind::String);
+        assert_eq!(JsonKind::of(bool::SHAPE), JsonKind::Boolean);
+        assert_eq!(JsonKind::of(u8::SHAPE), JsonKind::Integer);
+        assert_eq!(JsonKind::of(f32::SHAPE), JsonKind::Number);
+        assert_eq!(JsonKind::of(<Box<()>>::SHAPE), JsonKind::Null);
+        assert_eq!(JsonKind::of(<Vec<bool>>::SHAPE), JsonKind::Array);
+        assert_eq!(JsonKind::of(Point::SHAPE), JsonKind::Object);
+        assert_eq!(JsonKind::of(Color::SHAPE), JsonKind::String);
+        assert_eq!(JsonKind::of(<Option<String>>::SHAPE), JsonKind::String);
+        assert_eq!(JsonKind::of(<Option<bool>>::SHAPE), JsonKind::Boolean);
+    }
+
+    #[test]
+    fn test_split_doc() {
+        let doc = "Scales values.\n\n# Arguments\n\n* `values` - numbers to scale\n  in place\n- `factor`: multiplier\n\n# Errors\n\nNever.";
+        let (description, params) = split_doc(doc);
+        assert_eq!(description, "Scales values.\n\n# Errors\n\nNever.");
+        assert_eq!(
+            params,
+            vec![
+                ("values", "numbers to scale in place".to_string()),
+                ("factor", "multiplier".to_string())
+            ]
+        );
+    }
+}
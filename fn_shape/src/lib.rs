@@ -0,0 +1,941 @@
+//! Runtime types for the shapes generated by `#[facet_fn]` and `#[facet_impl]`.
+//!
+//! Every shape is a [`FunctionShape`], whatever the function's signature, so shapes can be
+//! stored together, compared and passed to ordinary functions.
+
+// Generated code refers to `::fn_shape`, which must also resolve inside this crate's tests
+extern crate self as fn_shape;
+
+#[doc(hidden)]
+pub use fn_shape_macro::__call_checked;
+pub use fn_shape_macro::{call, facet_fn, facet_impl, fn_shape};
+
+/// `Debug`, `PartialEq`, `Eq` and `Hash` for a newtype around a function pointer, comparing
+/// by address
+macro_rules! fn_pointer_impls {
+    ($name:ident) => {
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(stringify!($name))
+            }
+        }
+
+        impl PartialEq for $name {
+            fn eq(&self, other: &Self) -> bool {
+                core::ptr::fn_addr_eq(self.0, other.0)
+            }
+        }
+
+        impl Eq for $name {}
+
+        impl core::hash::Hash for $name {
+            fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+                self.0.hash(state);
+            }
+        }
+    };
+}
+
+pub mod dyn_call;
+pub use dyn_call::{CallError, DefaultValue, DynCall, DynResult};
+
+mod signature;
+
+pub mod named_args;
+pub use named_args::MissingArg;
+
+#[cfg(feature = "registry")]
+#[doc(hidden)]
+pub use inventory;
+#[cfg(feature = "registry")]
+pub mod registry;
+
+#[cfg(feature = "facet")]
+pub use facet;
+
+#[cfg(feature = "facet")]
+#[doc(hidden)]
+pub mod facet_probe;
+
+#[cfg(feature = "json")]
+pub mod json_call;
+#[cfg(feature = "json")]
+mod json_schema;
+#[cfg(feature = "json")]
+pub use json_call::{JsonCallError, JsonDecode, JsonEncode};
+
+#[cfg(feature = "rpc")]
+pub mod rpc;
+
+#[cfg(feature = "cli")]
+pub mod cli;
+
+#[cfg(feature = "repl")]
+pub mod repl;
+#[cfg(feature = "repl")]
+pub use repl::{DebugValue, ParseArg};
+
+/// Visibility and qualifiers as written on the function
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FnQualifiers {
+    /// Visibility, e.g. `pub(crate)`, or empty for private functions
+    pub visibility: &'static str,
+    pub is_const: bool,
+    pub is_async: bool,
+    pub is_unsafe: bool,
+    /// ABI of an `extern` function; a bare `extern` is `"C"`
+    pub abi: Option<&'static str>,
+}
+
+impl FnQualifiers {
+    /// A private function without qualifiers
+    pub const EMPTY: FnQualifiers = FnQualifiers {
+        visibility: "",
+        is_const: false,
+        is_async: false,
+        is_unsafe: false,
+        abi: None,
+    };
+}
+
+/// How a method takes `self`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Receiver {
+    /// `self` or `mut self`
+    Value,
+    /// `&self`
+    Ref,
+    /// `&mut self`
+    RefMut,
+    /// `self: T`, e.g. `self: Box<Self>`
+    Typed(&'static str),
+}
+
+/// A const generic parameter, e.g. `const N: usize`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ConstParamShape {
+    pub name: &'static str,
+    pub type_name: &'static str,
+}
+
+/// A Rust type's name as reported by [`core::any::type_name`], resolved when asked for so
+/// that generic shapes report the instantiated type
+#[derive(Clone, Copy)]
+pub struct TypeName(pub fn() -> &'static str);
+
+impl TypeName {
+    /// The type name, e.g. `alloc::string::String`
+    pub fn get(&self) -> &'static str {
+        (self.0)()
+    }
+}
+
+impl core::fmt::Debug for TypeName {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Debug::fmt(self.get(), f)
+    }
+}
+
+impl PartialEq for TypeName {
+    fn eq(&self, other: &Self) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl Eq for TypeName {}
+
+impl core::hash::Hash for TypeName {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.get().hash(state);
+    }
+}
+
+/// A type or const argument of a generic shape's instantiation, rendered when asked for,
+/// e.g. `alloc::string::String` or `4`
+#[derive(Clone, Copy)]
+pub struct GenericArg(pub fn() -> String);
+
+impl GenericArg {
+    /// The argument as text
+    pub fn get(&self) -> String {
+        (self.0)()
+    }
+}
+
+impl core::fmt::Debug for GenericArg {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Debug::fmt(&self.get(), f)
+    }
+}
+
+impl PartialEq for GenericArg {
+    fn eq(&self, other: &Self) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl Eq for GenericArg {}
+
+impl core::hash::Hash for GenericArg {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.get().hash(state);
+    }
+}
+
+/// How an argument is passed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ParamKind {
+    /// Passed by value
+    Owned,
+    /// `&T`
+    Ref,
+    /// `&mut T`
+    RefMut,
+    /// `impl Trait`, whose concrete type is chosen by the caller
+    ImplTrait,
+}
+
+/// A function parameter, not counting the receiver
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ParamShape {
+    /// The exported name: the binding cased by `rename_all`, if given, or `_{index}` for a
+    /// wildcard or destructuring pattern
+    pub name: &'static str,
+    /// The binding name, or the pattern spaced token by token, e.g. `( a , b )`
+    pub ident: &'static str,
+    /// Position in the parameter list, not counting the receiver
+    pub index: usize,
+    /// The type as written in the source, e.g. `& str`
+    pub type_name: &'static str,
+    /// The resolved type, or `None` when the type contains `impl Trait`
+    pub rust_type: Option<TypeName>,
+    pub kind: ParamKind,
+    /// Whether the parameter is bound by anything other than a plain identifier
+    pub is_pattern: bool,
+    /// Whether the type is an `Option`, recognised by the last segment of its path. Callers
+    /// may omit such parameters, which are then `None`.
+    pub is_optional: bool,
+    /// The default from `#[facet(default = ...)]` as written, `Default::default()` for a bare
+    /// `#[facet(default)]`; callers may omit parameters that have one
+    pub default: Option<&'static str>,
+    /// The value of an omitted argument for dynamic calls: the default, or `None` for an
+    /// `Option` parameter. `None` when neither applies or the function cannot be called
+    /// dynamically.
+    pub default_value: Option<DefaultValue>,
+    /// The facet shape of the type, if it implements `Facet`. Type parameters only have one
+    /// when bounded by `Facet`.
+    #[cfg(feature = "facet")]
+    pub facet_shape: Option<&'static facet::Shape>,
+    /// Decoder used by [`FunctionShape::call_json`], `None` when the type does not implement
+    /// `Facet` or the function cannot be called dynamically
+    #[cfg(feature = "json")]
+    pub json_decode: Option<JsonDecode>,
+    /// Parser used by [`repl::Repl`], `None` when the type does not implement `FromStr` or
+    /// the function cannot be called dynamically
+    #[cfg(feature = "repl")]
+    pub parse: Option<ParseArg>,
+}
+
+impl ParamShape {
+    /// The resolved type name from [`core::any::type_name`], if the type can be named
+    pub fn rust_type_name(&self) -> Option<&'static str> {
+        self.rust_type.map(|ty| ty.get())
+    }
+
+    /// Whether callers must give a value: the parameter has no default and is no `Option`
+    pub const fn is_required(&self) -> bool {
+        self.default.is_none() && !self.is_optional
+    }
+}
+
+/// A type nested in a return type, e.g. the `T` of `Result<T, E>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TypeShape {
+    /// The type as written in the source
+    pub type_name: &'static str,
+    /// The resolved type, or `None` when the type contains `impl Trait`
+    pub rust_type: Option<TypeName>,
+}
+
+/// How a return type is split up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReturnKind {
+    /// Any type other than `Result` or `Option`
+    Plain,
+    /// `Result<T, E>`; `err` is `None` for aliases such as `io::Result<T>`
+    Result {
+        ok: TypeShape,
+        err: Option<TypeShape>,
+    },
+    /// `Option<T>`
+    Option { some: TypeShape },
+}
+
+/// What a function returns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ReturnShape {
+    /// The type as written in the source, `()` when no return type is written
+    pub type_name: &'static str,
+    /// The resolved type, or `None` for `impl Trait` and `!`
+    pub rust_type: Option<TypeName>,
+    pub is_unit: bool,
+    /// `Result` and `Option` are recognised by the last segment of their path
+    pub kind: ReturnKind,
+    /// The facet shape of the type, if it implements `Facet`
+    #[cfg(feature = "facet")]
+    pub facet_shape: Option<&'static facet::Shape>,
+    /// Encoder used by [`FunctionShape::call_json`], `None` when the type does not implement
+    /// `Facet` or the function cannot be called dynamically
+    #[cfg(feature = "json")]
+    pub json_encode: Option<JsonEncode>,
+    /// Formatter used by [`repl::Repl`], `None` when the type does not implement `Debug` or
+    /// the function cannot be called dynamically
+    #[cfg(feature = "repl")]
+    pub debug: Option<DebugValue>,
+}
+
+impl ReturnShape {
+    /// The return shape of a function without a return type
+    pub const UNIT: ReturnShape = ReturnShape {
+        type_name: "()",
+        rust_type: Some(TypeName(core::any::type_name::<()>)),
+        is_unit: true,
+        kind: ReturnKind::Plain,
+        #[cfg(feature = "facet")]
+        facet_shape: Some(<() as facet::Facet>::SHAPE),
+        #[cfg(feature = "json")]
+        json_encode: json_call::JsonProbe::<()>::ENCODE,
+        #[cfg(feature = "repl")]
+        debug: repl::ReplProbe::<()>::DEBUG,
+    };
+
+    /// Whether the function returns a `Result`
+    pub const fn is_fallible(&self) -> bool {
+        matches!(self.kind, ReturnKind::Result { .. })
+    }
+
+    /// The resolved type name from [`core::any::type_name`], if the type can be named
+    pub fn rust_type_name(&self) -> Option<&'static str> {
+        self.rust_type.map(|ty| ty.get())
+    }
+}
+
+/// The shape of a function or method
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FunctionShape {
+    /// The name from `#[facet_fn(name = "...")]`, otherwise the Rust identifier
+    pub name: &'static str,
+    /// The Rust identifier
+    pub ident: &'static str,
+    /// Tags from `#[facet_fn(tags = [...])]`
+    pub tags: &'static [&'static str],
+    pub param_count: usize,
+    /// The exported parameter names, see [`ParamShape::name`]
+    pub param_names: &'static [&'static str],
+    pub params: &'static [ParamShape],
+    /// Declared type parameters, e.g. `T`
+    pub type_params: &'static [&'static str],
+    /// Declared lifetime parameters, e.g. `'a`
+    pub lifetimes: &'static [&'static str],
+    pub const_params: &'static [ConstParamShape],
+    /// The generic parameters as written, with their bounds, e.g. `< T : Clone >`; empty
+    /// without generics
+    pub generics: &'static str,
+    /// The type and const arguments of this instantiation in declaration order, lifetimes
+    /// left out
+    pub generic_args: &'static [GenericArg],
+    pub where_predicates: &'static [&'static str],
+    /// Doc comments, one line per `///` line
+    pub doc: &'static str,
+    pub qualifiers: FnQualifiers,
+    /// The receiver of a method, `None` for free and associated functions
+    pub receiver: Option<Receiver>,
+    /// The `Self` type of the impl block a method was shaped in
+    pub self_type: Option<&'static str>,
+    pub ret: ReturnShape,
+    /// Type-erased entry point used by [`FunctionShape::call_dyn`], `None` when the function
+    /// cannot be called dynamically
+    pub call: Option<DynCall>,
+}
+
+impl FunctionShape {
+    /// An empty shape for a private function without parameters
+    pub const fn new(name: &'static str) -> Self {
+        FunctionShape {
+            name,
+            ident: name,
+            tags: &[],
+            param_count: 0,
+            param_names: &[],
+            params: &[],
+            type_params: &[],
+            lifetimes: &[],
+            const_params: &[],
+            generics: "",
+            generic_args: &[],
+            where_predicates: &[],
+            doc: "",
+            qualifiers: FnQualifiers::EMPTY,
+            receiver: None,
+            self_type: None,
+            ret: ReturnShape::UNIT,
+            call: None,
+        }
+    }
+
+    /// The name qualified by the `Self` type for methods, e.g. `Counter::add`
+    pub fn qualified_name(&self) -> String {
+        match self.self_type {
+            Some(self_type) => format!("{self_type}::{}", self.name),
+            None => self.name.to_string(),
+        }
+    }
+
+    /// Whether the function was given `tag`
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.contains(&tag)
+    }
+
+    /// Whether this is the shape of a method taking `self`
+    pub const fn is_method(&self) -> bool {
+        self.receiver.is_some()
+    }
+
+    /// Call the function with boxed arguments, as described in [`dyn_call`]. The argument
+    /// count and types are checked before anything is moved out of `args`.
+    pub fn call_dyn(&self, args: &mut [Box<dyn core::any::Any>]) -> DynResult {
+        match self.call {
+            Some(call) => (call.0)(args),
+            None => Err(CallError::NotCallable { name: self.name }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[facet_fn]
+    fn add(x: i32, y: i32) -> i32 {
+        x + y
+    }
+
+    #[facet_fn]
+    fn shout(text: String) -> String {
+        text.to_uppercase()
+    }
+
+    struct Counter(u32);
+
+    #[facet_impl]
+    impl Counter {
+        fn bump(&mut self) {
+            self.0 += 1;
+        }
+    }
+
+    fn describe(shape: &FunctionShape) -> String {
+        format!("{}/{}", shape.name, shape.param_count)
+    }
+
+    #[test]
+    fn test_shapes_share_one_type() {
+        let shapes: Vec<FunctionShape> =
+            vec![fn_shape!(add), fn_shape!(shout), fn_shape!(Counter::bump)];
+        let described: Vec<_> = shapes.iter().map(describe).collect();
+        assert_eq!(described, vec!["add/2", "shout/1", "bump/0"]);
+        assert!(shapes[2].is_method());
+
+        let mut counter = Counter(0);
+        counter.bump();
+        assert_eq!(counter.0, 1);
+    }
+
+    #[test]
+    fn test_shapes_compare() {
+        assert_eq!(fn_shape!(add), fn_shape!(add));
+        assert_ne!(fn_shape!(add), fn_shape!(shout));
+        assert_eq!(add(1, 2), 3);
+        assert_eq!(shout("hi".to_string()), "HI");
+    }
+
+    mod math {
+        #[crate::facet_fn]
+        pub fn scale<T: core::ops::Mul<Output = T>>(value: T, factor: T) -> T {
+            value * factor
+        }
+    }
+
+    // Shapes are constant expressions, including those of generic functions in other modules
+    const SCALE_F32: FunctionShape = fn_shape!(math::scale<f32>);
+    static TABLE: [FunctionShape; 3] = [fn_shape!(add), SCALE_F32, fn_shape!(Counter::bump)];
+
+    #[test]
+    fn test_const_shapes() {
+        let described: Vec<_> = TABLE.iter().map(describe).collect();
+        assert_eq!(described, vec!["add/2", "scale/2", "bump/0"]);
+        assert_eq!(TABLE[1], math::SCALE_SHAPE::<f32>::SHAPE);
+        assert_eq!(TABLE[1].params[0].rust_type_name(), Some("f32"));
+        assert_eq!(math::scale(2, 3), 6);
+    }
+
+    #[facet_fn]
+    fn borrow_all<T: Clone>(items: &[T], out: &mut Vec<T>, _: impl Fn()) {
+        out.extend_from_slice(items);
+    }
+
+    #[test]
+    fn test_param_shapes() {
+        let shape = fn_shape!(borrow_all<String>);
+        let params = shape.params;
+        assert_eq!(params.len(), 3);
+        assert_eq!(params[1].name, "out");
+        assert_eq!(params[1].index, 1);
+        assert_eq!(params[0].kind, ParamKind::Ref);
+        assert_eq!(params[1].kind, ParamKind::RefMut);
+        assert_eq!(params[2].kind, ParamKind::ImplTrait);
+        assert_eq!(params[0].type_name, "& [T]");
+        assert_eq!(
+            params[1].rust_type_name(),
+            Some("&mut alloc::vec::Vec<alloc::string::String>")
+        );
+        assert_eq!(params[2].rust_type_name(), None);
+
+        let mut out = Vec::new();
+        borrow_all(&[1], &mut out, || {});
+        assert_eq!(out, vec![1]);
+    }
+
+    #[facet_fn]
+    fn retry(
+        url: String,
+        #[facet(default = 3)] times: u32,
+        #[facet(default)] quiet: bool,
+    ) -> usize {
+        if quiet { 0 } else { url.len() * times as usize }
+    }
+
+    #[test]
+    fn test_param_defaults() {
+        let params = fn_shape!(retry).params;
+        assert_eq!(params[0].default, None);
+        assert_eq!(params[1].default, Some("3"));
+        assert_eq!(params[2].default, Some("Default::default()"));
+        assert!(params[0].default_value.is_none());
+        let times = params[1].default_value.map(|default| (default.0)());
+        assert_eq!(
+            times.and_then(|v| v.downcast_ref::<u32>().copied()),
+            Some(3)
+        );
+        let quiet = params[2].default_value.map(|default| (default.0)());
+        assert_eq!(
+            quiet.and_then(|v| v.downcast_ref::<bool>().copied()),
+            Some(false)
+        );
+        assert_eq!(retry("ab".to_string(), 2, false), 4);
+
+        #[cfg(feature = "json")]
+        {
+            let shape = fn_shape!(retry);
+            assert_eq!(shape.call_json(r#"{"url": "ab"}"#).as_deref(), Ok("6"));
+            assert_eq!(shape.call_json(r#"["ab", 1]"#).as_deref(), Ok("2"));
+            assert_eq!(
+                shape.call_json(r#"{"times": 1}"#),
+                Err(JsonCallError::MissingArg { name: "url" })
+            );
+        }
+    }
+
+    #[test]
+    fn test_new_is_empty() {
+        let shape = FunctionShape::new("empty");
+        assert_eq!(shape.param_count, 0);
+        assert_eq!(shape.qualifiers, FnQualifiers::EMPTY);
+        assert!(!shape.is_method());
+        assert!(shape.ret.is_unit);
+    }
+
+    #[facet_fn]
+    fn parse_port(text: &str) -> Result<u16, core::num::ParseIntError> {
+        text.parse()
+    }
+
+    #[test]
+    fn test_return_shapes() {
+        let ret = fn_shape!(parse_port).ret;
+        assert!(ret.is_fallible());
+        assert!(!ret.is_unit);
+        assert_eq!(
+            ret.type_name,
+            "Result < u16, core :: num :: ParseIntError >"
+        );
+        let ReturnKind::Result { ok, err } = ret.kind else {
+            panic!("expected a Result return, got {:?}", ret.kind);
+        };
+        assert_eq!(ok.type_name, "u16");
+        assert_eq!(ok.rust_type.map(|ty| ty.get()), Some("u16"));
+        assert_eq!(
+            err.map(|err| err.type_name),
+            Some("core :: num :: ParseIntError")
+        );
+
+        assert_eq!(fn_shape!(add).ret.kind, ReturnKind::Plain);
+        assert_eq!(fn_shape!(add).ret.rust_type_name(), Some("i32"));
+        assert_eq!(fn_shape!(Counter::bump).ret, ReturnShape::UNIT);
+        assert_eq!(parse_port("80"), Ok(80));
+    }
+
+    #[test]
+    fn test_call_dyn() {
+        let mut args: Vec<Box<dyn core::any::Any>> = vec![Box::new(2i32), Box::new(3i32)];
+        let sum = fn_shape!(add).call_dyn(&mut args).expect("should call");
+        assert_eq!(sum.downcast_ref::<i32>(), Some(&5));
+
+        let mut counter: Vec<Box<dyn core::any::Any>> = vec![Box::new(Counter(1))];
+        fn_shape!(Counter::bump)
+            .call_dyn(&mut counter)
+            .expect("should call");
+        assert_eq!(counter[0].downcast_ref::<Counter>().map(|c| c.0), Some(2));
+    }
+
+    #[test]
+    fn test_call_dyn_errors() {
+        let shape = fn_shape!(add);
+        let mut args: Vec<Box<dyn core::any::Any>> = vec![Box::new(2i32)];
+        assert_eq!(
+            shape.call_dyn(&mut args).err(),
+            Some(CallError::ArgCount {
+                expected: 2,
+                got: 1
+            })
+        );
+
+        // A wrong type is reported before any argument is moved out
+        let mut args: Vec<Box<dyn core::any::Any>> = vec![Box::new(2i32), Box::new("3")];
+        assert_eq!(
+            shape.call_dyn(&mut args).err(),
+            Some(CallError::ArgType {
+                index: 1,
+                expected: "i32"
+            })
+        );
+        assert!(args[0].is::<i32>());
+
+        let shape = fn_shape!(shout);
+        let mut args: Vec<Box<dyn core::any::Any>> = vec![Box::new("a".to_string())];
+        shape.call_dyn(&mut args).expect("should call");
+        assert_eq!(
+            shape.call_dyn(&mut args).err(),
+            Some(CallError::ArgMoved { index: 0 })
+        );
+
+        // Generic functions cannot be called dynamically
+        let shape = fn_shape!(borrow_all<String>);
+        assert_eq!(
+            shape.call_dyn(&mut []).err(),
+            Some(CallError::NotCallable { name: "borrow_all" })
+        );
+    }
+
+    #[facet_fn(name = "retryDelay", rename_all = "camelCase", tags = ["net", "retry"])]
+    fn retry_delay(attempt: u32, base_delay_ms: u32) -> u32 {
+        base_delay_ms << attempt
+    }
+
+    #[facet_fn(skip_module, skip_args, rename_all = "kebab-case")]
+    fn unwrapped((a, b): (u8, u8), extra_value: u8) -> u8 {
+        a + b + extra_value
+    }
+
+    #[facet_fn(skip_export)]
+    fn unexported() -> u8 {
+        1
+    }
+
+    #[facet_fn(skip_shape)]
+    fn shapeless(x: u8) -> u8 {
+        x
+    }
+
+    #[test]
+    fn test_shape_args() {
+        let shape = fn_shape!(retry_delay);
+        assert_eq!(shape.name, "retryDelay");
+        assert_eq!(shape.ident, "retry_delay");
+        assert_eq!(shape.tags, &["net", "retry"]);
+        assert!(shape.has_tag("net") && !shape.has_tag("math"));
+        assert_eq!(shape.param_names, &["attempt", "baseDelayMs"]);
+        assert_eq!(shape.params[1].ident, "base_delay_ms");
+        assert_eq!(retry_delay(2, 10), 40);
+
+        // The function is kept as written, and patterns are not renamed
+        let shape = fn_shape!(unwrapped);
+        assert_eq!(shape.param_names, &["_0", "extra-value"]);
+        assert_eq!(shape.params[0].ident, "( a , b )");
+        let mut args: Vec<Box<dyn core::any::Any>> = vec![Box::new((1u8, 2u8)), Box::new(3u8)];
+        let sum = shape.call_dyn(&mut args).expect("should call");
+        assert_eq!(sum.downcast_ref::<u8>(), Some(&6));
+
+        assert_eq!(unexported(), 1);
+        assert_eq!(ShapelessArgs { x: 4 }.call(), shapeless(4));
+
+        #[cfg(feature = "json")]
+        assert_eq!(
+            fn_shape!(retry_delay)
+                .call_json(r#"{"attempt": 1, "baseDelayMs": 5}"#)
+                .as_deref(),
+            Ok("10")
+        );
+    }
+
+    #[cfg(feature = "registry")]
+    #[facet_fn(register = false)]
+    fn hidden() {}
+
+    #[cfg(feature = "registry")]
+    struct Gauge;
+
+    #[cfg(feature = "registry")]
+    #[facet_impl(register = false)]
+    impl Gauge {
+        #[facet_fn(register = true)]
+        fn read() -> u8 {
+            1
+        }
+
+        #[facet_fn]
+        fn read_raw() -> u8 {
+            2
+        }
+    }
+
+    #[cfg(feature = "registry")]
+    #[test]
+    fn test_registry() {
+        let names: Vec<_> = registry::all()
+            .iter()
+            .map(FunctionShape::qualified_name)
+            .collect();
+        assert!(names.contains(&"add".to_string()));
+        assert!(names.contains(&"Counter::bump".to_string()));
+        // Generic and opted-out functions are not registered
+        assert!(!names.contains(&"borrow_all".to_string()));
+        assert!(!names.contains(&"hidden".to_string()));
+        hidden();
+
+        // A method's marker overrides the arguments of its impl block
+        assert!(names.contains(&"Gauge::read".to_string()));
+        assert!(!names.contains(&"Gauge::read_raw".to_string()));
+        assert_eq!(Gauge::read() + Gauge::read_raw(), 3);
+
+        assert_eq!(registry::get("add"), Some(fn_shape!(add)));
+        assert_eq!(
+            registry::get("Counter::bump"),
+            Some(fn_shape!(Counter::bump))
+        );
+        assert_eq!(registry::get("bump"), None);
+
+        // Shapes are registered under their exported name, even when not re-exported
+        assert_eq!(registry::get("retryDelay"), Some(fn_shape!(retry_delay)));
+        assert_eq!(registry::get("retry_delay"), None);
+        assert!(registry::get("unexported").is_some());
+        assert!(registry::get("shapeless").is_none());
+        let tagged: Vec<_> = registry::tagged("retry").iter().map(|s| s.name).collect();
+        assert_eq!(tagged, vec!["retryDelay"]);
+    }
+
+    #[cfg(feature = "facet")]
+    #[facet_fn]
+    fn tally<T: for<'f> facet::Facet<'f>>(
+        words: Vec<String>,
+        _seed: T,
+        _: impl Fn(),
+    ) -> Option<u64> {
+        Some(words.len() as u64)
+    }
+
+    #[cfg(feature = "facet")]
+    #[test]
+    fn test_facet_shapes() {
+        let shape = fn_shape!(tally<u8>);
+        let facet_names: Vec<_> = shape
+            .params
+            .iter()
+            .map(|p| p.facet_shape.map(|s| s.to_string()))
+            .collect();
+        assert_eq!(
+            facet_names,
+            vec![
+                Some("Vec<String>".to_string()),
+                Some("u8".to_string()),
+                None
+            ]
+        );
+        assert_eq!(
+            shape.ret.facet_shape.map(|s| s.to_string()).as_deref(),
+            Some("Option<u64>")
+        );
+        // Unbounded type parameters and types without a `Facet` impl have no facet shape
+        let shape = fn_shape!(borrow_all<String>);
+        assert!(shape.params[0].facet_shape.is_none());
+        assert_eq!(
+            fn_shape!(Counter::bump).ret.facet_shape,
+            Some(<() as facet::Facet>::SHAPE)
+        );
+        assert_eq!(tally(vec![], 0u8, || {}), Some(0));
+    }
+
+    #[cfg(feature = "json")]
+    #[facet_fn]
+    fn scale(values: Vec<u32>, factor: u32) -> Option<Vec<u32>> {
+        values.iter().map(|v| v.checked_mul(factor)).collect()
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_call_json() {
+        let shape = fn_shape!(add);
+        assert_eq!(shape.call_json(r#"{"x": 2, "y": 3}"#).as_deref(), Ok("5"));
+        assert_eq!(shape.call_json("[2, 3]").as_deref(), Ok("5"));
+
+        let shape = fn_shape!(scale);
+        assert_eq!(
+            shape
+                .call_json(r#"{"factor": 2, "values": [1, 2]}"#)
+                .as_deref(),
+            Ok("[2,4]")
+        );
+        assert_eq!(
+            shape.call_json(r#"[[4294967295], 2]"#).as_deref(),
+            Ok("null")
+        );
+        assert_eq!(scale(vec![1], 3), Some(vec![3]));
+    }
+
+    #[cfg(feature = "json")]
+    #[facet_fn]
+    fn greeting(name: String, title: Option<String>) -> String {
+        match title {
+            Some(title) => format!("{title} {name}"),
+            None => name,
+        }
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_call_json_optional() {
+        // An omitted `Option` argument is `None`, as the schema allows
+        let shape = fn_shape!(greeting);
+        assert!(shape.params[1].is_optional && !shape.params[1].is_required());
+        assert_eq!(
+            shape.call_json(r#"{"name": "Ada"}"#).as_deref(),
+            Ok(r#""Ada""#)
+        );
+        assert_eq!(shape.call_json(r#"["Ada"]"#).as_deref(), Ok(r#""Ada""#));
+        assert_eq!(
+            shape
+                .call_json(r#"{"name": "Ada", "title": "Dr."}"#)
+                .as_deref(),
+            Ok(r#""Dr. Ada""#)
+        );
+        assert_eq!(
+            shape.call_json("[]"),
+            Err(JsonCallError::MissingArg { name: "name" })
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[facet_fn]
+    fn loud(text: &str) -> String {
+        text.to_uppercase()
+    }
+
+    #[cfg(feature = "json")]
+    #[facet_fn]
+    fn byte_sum(bytes: &[u8]) -> u32 {
+        bytes.iter().map(|&b| u32::from(b)).sum()
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_call_json_str() {
+        // `&str` arguments are decoded from JSON strings; other references cannot be
+        let shape = fn_shape!(loud);
+        assert!(shape.is_json_callable());
+        assert_eq!(
+            shape.call_json(r#"{"text": "hi"}"#).as_deref(),
+            Ok(r#""HI""#)
+        );
+        assert!(matches!(
+            shape.call_json("[[1]]"),
+            Err(JsonCallError::ArgType { name: "text", .. })
+        ));
+        assert!(!fn_shape!(byte_sum).is_json_callable());
+        assert_eq!(
+            fn_shape!(byte_sum).call_json("[[1, 2]]"),
+            Err(JsonCallError::NotDecodable { name: "bytes" })
+        );
+        assert_eq!(byte_sum(&[1, 2]), 3);
+    }
+
+    #[cfg(feature = "json")]
+    #[facet_fn]
+    fn count_of(counter: Counter) -> u32 {
+        counter.0
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_call_json_errors() {
+        let shape = fn_shape!(add);
+        assert_eq!(
+            shape.call_json(r#"{"x": 2}"#),
+            Err(JsonCallError::MissingArg { name: "y" })
+        );
+        assert_eq!(
+            shape.call_json("[2]"),
+            Err(JsonCallError::MissingArg { name: "y" })
+        );
+        assert_eq!(
+            shape.call_json(r#"{"x": 2, "y": 3, "z": 4}"#),
+            Err(JsonCallError::UnknownArg {
+                name: "z".to_string()
+            })
+        );
+        assert_eq!(
+            shape.call_json("[1, 2, 3]"),
+            Err(JsonCallError::TooManyArgs {
+                expected: 2,
+                got: 3
+            })
+        );
+        assert_eq!(shape.call_json("2"), Err(JsonCallError::NotArguments));
+        assert!(matches!(
+            shape.call_json("[2,"),
+            Err(JsonCallError::InvalidJson(_))
+        ));
+
+        let err = shape.call_json(r#"[2, "three"]"#).unwrap_err();
+        assert!(matches!(err, JsonCallError::ArgType { name: "y", .. }));
+        assert!(err.to_string().starts_with("invalid argument `y`"));
+
+        // Types without a `Facet` impl cannot be decoded
+        assert_eq!(
+            fn_shape!(count_of).call_json("[1]"),
+            Err(JsonCallError::NotDecodable { name: "counter" })
+        );
+        assert_eq!(count_of(Counter(4)), 4);
+
+        // `impl Trait` parameters, generic functions and methods taking `self` are refused
+        assert_eq!(
+            fn_shape!(borrow_all<String>).call_json("[]"),
+            Err(JsonCallError::NotCallable {
+                function: "borrow_all"
+            })
+        );
+        assert_eq!(
+            fn_shape!(Counter::bump).call_json("[]"),
+            Err(JsonCallError::NotCallable { function: "bump" })
+        );
+    }
+}
@@ -0,0 +1,136 @@
+//! Named-argument calls.
+//!
+//! `#[facet_fn]` gives a function `add(x: i32, y: i32)` an `AddArgs` struct with public `x`
+//! and `y` fields, a `call` method, and a builder with `with_x` and `with_y` setters whose
+//! `build` reports the first argument left unset. Generic functions get a generic struct, and `impl Trait` parameters become
+//! type parameters of it. [`call!`](crate::call) fills the struct from named arguments, so
+//! `call!(add, y = 3, x = 2)` is `add(2, 3)`, and reports unknown, repeated and missing
+//! names as compile errors. It works on functions of the crate it is used in. Parameters
+//! with `#[facet(default)]` may be left unset in both.
+
+use core::fmt;
+
+/// An argument left unset on an args builder
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MissingArg {
+    pub function: &'static str,
+    pub name: &'static str,
+}
+
+impl fmt::Display for MissingArg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "missing argument `{}` of `{}`", self.name, self.function)
+    }
+}
+
+impl std::error::Error for MissingArg {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{call, facet_fn};
+
+    #[facet_fn(register = false)]
+    fn label(text: &str, width: usize, fill: char) -> String {
+        let padding = width.saturating_sub(text.chars().count());
+        format!("{text}{}", String::from(fill).repeat(padding))
+    }
+
+    #[test]
+    fn test_named_args() {
+        let fill = '.';
+        assert_eq!(call!(label, width = 4, fill, text = "ab"), "ab..");
+
+        let args = LabelArgs::builder()
+            .with_fill('-')
+            .with_text("x")
+            .with_width(3)
+            .build();
+        assert_eq!(args.map(LabelArgs::call).as_deref(), Ok("x--"));
+
+        let err = LabelArgs::builder().with_text("x").build().err();
+        assert_eq!(
+            err,
+            Some(MissingArg {
+                function: "label",
+                name: "width"
+            })
+        );
+        assert_eq!(
+            err.unwrap().to_string(),
+            "missing argument `width` of `label`"
+        );
+    }
+
+    #[facet_fn(register = false)]
+    fn deploy(target: &str, build: u32) -> String {
+        format!("{target}#{build}")
+    }
+
+    #[test]
+    fn test_build_param() {
+        // Setters are prefixed, so a parameter may be called `build`
+        assert_eq!(call!(deploy, build = 7, target = "prod"), "prod#7");
+        let args = DeployArgs::builder()
+            .with_build(1)
+            .with_target("dev")
+            .build();
+        assert_eq!(args.map(DeployArgs::call).as_deref(), Ok("dev#1"));
+    }
+
+    #[facet_fn(register = false)]
+    fn pick<'a, T, const N: usize>(items: &'a [T; N], index: usize) -> Option<&'a T> {
+        items.get(index)
+    }
+
+    #[facet_fn(register = false)]
+    fn longer<'a>(a: &'a str, b: &'a str, tie: &str) -> &'a str {
+        match a.len().cmp(&b.len()) {
+            core::cmp::Ordering::Less => b,
+            core::cmp::Ordering::Equal if tie == "b" => b,
+            _ => a,
+        }
+    }
+
+    #[facet_fn(register = false)]
+    fn fold(values: &[u8], step: impl Fn(u32, u8) -> u32) -> u32 {
+        values.iter().fold(0, |total, &value| step(total, value))
+    }
+
+    #[facet_fn(register = false)]
+    fn parse_or<T: core::str::FromStr>(text: &str, fallback: u8) -> Result<T, u8> {
+        text.parse().map_err(|_| fallback)
+    }
+
+    #[test]
+    fn test_generic_args() {
+        let items = ["a", "b"];
+        assert_eq!(call!(pick, index = 1, items = &items), Some(&"b"));
+        let args: PickArgs<'_, &str, 2> = PickArgs {
+            items: &items,
+            index: 0,
+        };
+        assert_eq!(args.call(), Some(&"a"));
+
+        // Elided lifetimes get a lifetime of their own when the function declares `'a`
+        let args: LongerArgs<'_, '_> = LongerArgs {
+            a: "ab",
+            b: "cd",
+            tie: "b",
+        };
+        assert_eq!(args.call(), "cd");
+
+        // `impl Trait` parameters become type parameters
+        let step = |total: u32, value: u8| total * 10 + u32::from(value);
+        assert_eq!(call!(fold, values = &[1, 2], step), 12);
+
+        // Type parameters that no parameter mentions are kept in a marker field
+        let parsed: Result<u16, u8> = call!(parse_or, text = "12", fallback = 0);
+        assert_eq!(parsed, Ok(12));
+        let args = ParseOrArgs::<u16>::builder()
+            .with_text("x")
+            .with_fallback(3)
+            .build();
+        assert_eq!(args.map(ParseOrArgs::call), Ok(Err(3)));
+    }
+}
@@ -0,0 +1,46 @@
+//! Every shaped function in the binary, collected at link time.
+//!
+//! `#[facet_fn]` and `#[facet_impl]` register each non-generic function and method unless
+//! given `register = false` or `skip_registry`. Shapes from every linked crate are included.
+
+use crate::FunctionShape;
+
+/// A registry entry; created by the macros
+#[doc(hidden)]
+pub struct Registered {
+    shape: &'static FunctionShape,
+}
+
+impl Registered {
+    pub const fn new(shape: &'static FunctionShape) -> Self {
+        Registered { shape }
+    }
+}
+
+inventory::collect!(Registered);
+
+/// Every registered shape, ordered by qualified name, e.g. `Counter::add`
+pub fn all() -> Vec<FunctionShape> {
+    let mut shapes: Vec<_> = inventory::iter::<Registered>
+        .into_iter()
+        .map(|entry| *entry.shape)
+        .collect();
+    shapes.sort_by_cached_key(FunctionShape::qualified_name);
+    shapes
+}
+
+/// The shape registered under `name`: a function name such as `add`, or `Type::method` for
+/// methods
+pub fn get(name: &str) -> Option<FunctionShape> {
+    inventory::iter::<Registered>
+        .into_iter()
+        .map(|entry| *entry.shape)
+        .find(|shape| shape.qualified_name() == name)
+}
+
+/// Every registered shape given `tag` in `#[facet_fn(tags = [...])]`, ordered like [`all`]
+pub fn tagged(tag: &str) -> Vec<FunctionShape> {
+    let mut shapes = all();
+    shapes.retain(|shape| shape.has_tag(tag));
+    shapes
+}
@@ -0,0 +1,612 @@
+//! An interactive read-eval-print loop over shaped functions.
+//!
+//! A line such as `add 2 3` or `add x=2 y=3` calls a function with literal arguments, each
+//! parsed into its parameter type with `FromStr`, and prints the result with `Debug`.
+//! Arguments containing spaces are quoted: `greet "Jane Doe"`. `Option` parameters take
+//! `None`, `Some(x)` or `x`, and may be left out like parameters with a default. `&str`
+//! parameters are parsed by leaking the text, which is fine for an interactive session.
+//!
+//! `help` or `list` lists the functions, `help <name>` shows one, and `quit` or `exit` ends
+//! the loop. A function called `help` or `list` takes the name over from the built-in.
+//!
+//! Lines are read whole, so there is no completion while typing. Submitting a line that ends
+//! in a tab prints the completions of its last word instead of running it, and
+//! [`Repl::completions`] offers the same candidates to a line editor.
+
+use core::any::Any;
+use core::fmt;
+use core::marker::PhantomData;
+use core::str::FromStr;
+use std::io::{self, BufRead, Write};
+
+use crate::signature::tidy;
+use crate::{CallError, FunctionShape};
+
+/// Parses an argument literal into a boxed parameter value
+#[derive(Clone, Copy)]
+pub struct ParseArg(pub fn(&str) -> Result<Box<dyn Any>, String>);
+
+/// Formats a boxed return value with `Debug`
+#[derive(Clone, Copy)]
+pub struct DebugValue(pub fn(&dyn Any) -> String);
+
+fn_pointer_impls!(ParseArg);
+fn_pointer_impls!(DebugValue);
+
+fn parse<T>(text: &str) -> Result<Box<dyn Any>, String>
+where
+    T: FromStr + 'static,
+    T::Err: fmt::Display,
+{
+    text.parse::<T>()
+        .map(|value| Box::new(value) as Box<dyn Any>)
+        .map_err(|err| err.to_string())
+}
+
+fn parse_option<T>(text: &str) -> Result<Box<dyn Any>, String>
+where
+    T: FromStr + 'static,
+    T::Err: fmt::Display,
+{
+    let text = text.trim();
+    if text == "None" {
+        return Ok(Box::new(None::<T>));
+    }
+    let inner = text
+        .strip_prefix("Some(")
+        .and_then(|inner| inner.strip_suffix(')'))
+        .unwrap_or(text);
+    inner
+        .parse::<T>()
+        .map(|value| Box::new(Some(value)) as Box<dyn Any>)
+        .map_err(|err| err.to_string())
+}
+
+/// The parser of `&str` parameters, which leaks `text` to give it a `'static` lifetime
+#[doc(hidden)]
+pub fn parse_str(text: &str) -> Result<Box<dyn Any>, String> {
+    let text: &'static str = Box::leak(text.to_owned().into_boxed_str());
+    Ok(Box::new(text))
+}
+
+fn debug<T: fmt::Debug + 'static>(value: &dyn Any) -> String {
+    match value.downcast_ref::<T>() {
+        Some(value) => format!("{value:?}"),
+        None => format!("<{}>", core::any::type_name::<T>()),
+    }
+}
+
+/// Resolves to the parser and formatter of `T`, like [`crate::facet_probe::FacetProbe`]
+#[doc(hidden)]
+pub struct ReplProbe<T: ?Sized>(PhantomData<T>);
+
+impl<T> ReplProbe<T>
+where
+    T: FromStr + 'static,
+    T::Err: fmt::Display,
+{
+    pub const PARSE: Option<ParseArg> = Some(ParseArg(parse::<T>));
+    /// The parser of `Option<T>`
+    pub const OPTION: Option<ParseArg> = Some(ParseArg(parse_option::<T>));
+}
+
+impl<T: fmt::Debug + 'static> ReplProbe<T> {
+    pub const DEBUG: Option<DebugValue> = Some(DebugValue(debug::<T>));
+}
+
+/// The fallback for types that do not implement `FromStr` or `Debug`
+#[doc(hidden)]
+pub trait NoRepl {
+    const PARSE: Option<ParseArg> = None;
+    const OPTION: Option<ParseArg> = None;
+    const DEBUG: Option<DebugValue> = None;
+}
+
+impl<T: ?Sized> NoRepl for ReplProbe<T> {}
+
+/// Why a line could not be run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplError {
+    /// No function has this name
+    UnknownFunction(String),
+    /// A quoted argument is not closed
+    UnterminatedQuote,
+    /// `name=value` names no parameter
+    UnknownArg { name: String },
+    /// Parameter `name` was given twice
+    DuplicateArg { name: &'static str },
+    /// More positional arguments than parameters
+    TooManyArgs { expected: usize, got: usize },
+    /// No value was given for parameter `name`
+    MissingArg { name: &'static str },
+    /// The type of parameter `name` cannot be parsed from a literal
+    NotParseable {
+        name: &'static str,
+        type_name: String,
+    },
+    /// The literal for parameter `name` does not parse into its type
+    InvalidArg { name: &'static str, message: String },
+    /// The dynamic call failed
+    Call(CallError),
+}
+
+impl fmt::Display for ReplError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplError::UnknownFunction(name) => write!(f, "unknown function `{name}`"),
+            ReplError::UnterminatedQuote => f.write_str("unterminated quote"),
+            ReplError::UnknownArg { name } => write!(f, "unknown argument `{name}`"),
+            ReplError::DuplicateArg { name } => write!(f, "argument `{name}` given twice"),
+            ReplError::TooManyArgs { expected, got } => {
+                write!(f, "expected at most {expected} arguments, got {got}")
+            }
+            ReplError::MissingArg { name } => write!(f, "missing argument `{name}`"),
+            ReplError::NotParseable { name, type_name } => {
+                write!(
+                    f,
+                    "argument `{name}` of type `{type_name}` cannot be parsed from a literal"
+                )
+            }
+            ReplError::InvalidArg { name, message } => {
+                write!(f, "invalid argument `{name}`: {message}")
+            }
+            ReplError::Call(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ReplError {}
+
+impl From<CallError> for ReplError {
+    fn from(err: CallError) -> Self {
+        ReplError::Call(err)
+    }
+}
+
+/// A REPL over a set of shapes, looked up by qualified name
+pub struct Repl {
+    shapes: Vec<FunctionShape>,
+}
+
+impl Repl {
+    pub fn new(shapes: Vec<FunctionShape>) -> Self {
+        Repl { shapes }
+    }
+
+    /// Read lines from `input` and write prompts and results to `output` until `quit`,
+    /// `exit` or the end of input
+    pub fn run(&self, mut input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        loop {
+            write!(output, "> ")?;
+            output.flush()?;
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                writeln!(output)?;
+                return Ok(());
+            }
+            let line = line.trim_end_matches(['\n', '\r']);
+            if let Some(partial) = line.strip_suffix('\t') {
+                writeln!(output, "{}", self.completions(partial).join("  "))?;
+                continue;
+            }
+            if matches!(line.trim(), "quit" | "exit") {
+                return Ok(());
+            }
+            match self.eval(line) {
+                Ok(result) if result.is_empty() => {}
+                Ok(result) => writeln!(output, "{result}")?,
+                Err(err) => writeln!(output, "error: {err}")?,
+            }
+        }
+    }
+
+    /// Run one line and return what it prints: the `Debug` output of the result, nothing
+    /// for `()`, or the requested help
+    pub fn eval(&self, line: &str) -> Result<String, ReplError> {
+        let mut args = split_args(line)?.into_iter();
+        let Some(first) = args.next() else {
+            return Ok(String::new());
+        };
+        let command = match first.name {
+            Some(name) => return Err(ReplError::UnknownFunction(format!("{name}={}", first.text))),
+            None => first.text,
+        };
+        let args: Vec<_> = args.collect();
+
+        if self.is_builtin(&command) {
+            return match args.first() {
+                Some(name) => self.help(&self.function(&name.text)?),
+                None => Ok(self.list()),
+            };
+        }
+        let shape = self.function(&command)?;
+        if shape.call.is_none() || shape.receiver.is_some() {
+            return Err(CallError::NotCallable { name: shape.name }.into());
+        }
+
+        let mut texts: Vec<Option<String>> = vec![None; shape.params.len()];
+        let positional = args.iter().filter(|arg| arg.name.is_none()).count();
+        for arg in args {
+            let index = match &arg.name {
+                Some(name) => shape
+                    .params
+                    .iter()
+                    .position(|param| param.name == name)
+                    .ok_or_else(|| ReplError::UnknownArg { name: name.clone() })?,
+                None => texts
+                    .iter()
+                    .position(Option::is_none)
+                    .ok_or(ReplError::TooManyArgs {
+                        expected: shape.params.len(),
+                        got: positional,
+                    })?,
+            };
+            if texts[index].is_some() {
+                return Err(ReplError::DuplicateArg {
+                    name: shape.params[index].name,
+                });
+            }
+            texts[index] = Some(arg.text);
+        }
+
+        let mut values = Vec::with_capacity(texts.len());
+        for (param, text) in shape.params.iter().zip(texts) {
+            let text = match (text, param.default_value) {
+                (Some(text), _) => text,
+                (None, Some(default)) => {
+                    values.push((default.0)());
+                    continue;
+                }
+                (None, None) => return Err(ReplError::MissingArg { name: param.name }),
+            };
+            let parse = param.parse.ok_or_else(|| ReplError::NotParseable {
+                name: param.name,
+                type_name: tidy(param.type_name),
+            })?;
+            let value = (parse.0)(&text).map_err(|message| ReplError::InvalidArg {
+                name: param.name,
+                message,
+            })?;
+            values.push(value);
+        }
+
+        let result = shape.call_dyn(&mut values)?;
+        Ok(match shape.ret.debug {
+            _ if shape.ret.is_unit => String::new(),
+            Some(debug) => (debug.0)(&*result),
+            None => format!("<{}>", tidy(shape.ret.type_name)),
+        })
+    }
+
+    /// The completions of the last word of `line`: function names for the first word and
+    /// `name=` for parameters not yet given by name
+    pub fn completions(&self, line: &str) -> Vec<String> {
+        let words: Vec<_> = line.split_whitespace().collect();
+        let partial = if line.ends_with(char::is_whitespace) {
+            ""
+        } else {
+            words.last().copied().unwrap_or("")
+        };
+        let complete_args = words.len() > 1 || (words.len() == 1 && partial.is_empty());
+
+        let mut candidates: Vec<String> = match words.first() {
+            Some(command) if self.is_builtin(command) && complete_args && words.len() <= 2 => self
+                .shapes
+                .iter()
+                .map(FunctionShape::qualified_name)
+                .collect(),
+            Some(function) if complete_args => {
+                let Ok(shape) = self.function(function) else {
+                    return Vec::new();
+                };
+                if partial.contains('=') {
+                    return Vec::new();
+                }
+                shape
+                    .params
+                    .iter()
+                    .filter(|param| {
+                        let given = format!("{}=", param.name);
+                        !words[1..].iter().any(|word| word.starts_with(&given))
+                    })
+                    .map(|param| format!("{}=", param.name))
+                    .collect()
+            }
+            _ => self
+                .shapes
+                .iter()
+                .map(FunctionShape::qualified_name)
+                .chain(["help", "list", "quit", "exit"].map(String::from))
+                .collect(),
+        };
+        candidates.retain(|candidate| candidate.starts_with(partial));
+        candidates.sort();
+        candidates.dedup();
+        candidates
+    }
+
+    /// One signature per line
+    pub fn list(&self) -> String {
+        self.shapes
+            .iter()
+            .map(signature)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn help(&self, shape: &FunctionShape) -> Result<String, ReplError> {
+        Ok(match shape.doc.trim() {
+            "" => signature(shape),
+            doc => format!("{}\n\n{doc}", signature(shape)),
+        })
+    }
+
+    /// Whether `command` is the built-in `help` or `list`, which a function of the same name
+    /// shadows
+    fn is_builtin(&self, command: &str) -> bool {
+        matches!(command, "help" | "list") && self.function(command).is_err()
+    }
+
+    fn function(&self, name: &str) -> Result<FunctionShape, ReplError> {
+        self.shapes
+            .iter()
+            .find(|shape| shape.qualified_name() == name)
+            .copied()
+            .ok_or_else(|| ReplError::UnknownFunction(name.to_string()))
+    }
+}
+
+/// `name(a: A, b: B) -> R`
+fn signature(shape: &FunctionShape) -> String {
+    let params: Vec<_> = shape
+        .params
+        .iter()
+        .map(|param| format!("{}: {}", param.name, tidy(param.type_name)))
+        .collect();
+    let mut signature = format!("{}({})", shape.qualified_name(), params.join(", "));
+    if !shape.ret.is_unit {
+        signature.push_str(" -> ");
+        signature.push_str(&tidy(shape.ret.type_name));
+    }
+    signature
+}
+
+/// A word of a line, `name=text` or just `text`, with quotes removed
+struct Arg {
+    name: Option<String>,
+    text: String,
+}
+
+/// Split a line at whitespace outside of quotes. `"` and `'` quote, and `\` escapes the next
+/// character inside quotes.
+fn split_args(line: &str) -> Result<Vec<Arg>, ReplError> {
+    let mut args = Vec::new();
+    let mut name = None;
+    let mut text = String::new();
+    let mut started = false;
+    let mut quoted = false;
+    let mut quote = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), '\\') => match chars.next() {
+                Some('n') => text.push('\n'),
+                Some('t') => text.push('\t'),
+                Some(c) => text.push(c),
+                None => return Err(ReplError::UnterminatedQuote),
+            },
+            (Some(_), c) => text.push(c),
+            (None, c) if c.is_whitespace() => {
+                if started {
+                    args.push(Arg {
+                        name: name.take(),
+                        text: core::mem::take(&mut text),
+                    });
+                }
+                started = false;
+                quoted = false;
+            }
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                started = true;
+                quoted = true;
+            }
+            (None, '=') if name.is_none() && !quoted && is_ident(&text) => {
+                name = Some(core::mem::take(&mut text));
+            }
+            (None, c) => {
+                text.push(c);
+                started = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return Err(ReplError::UnterminatedQuote);
+    }
+    if started {
+        args.push(Arg { name, text });
+    }
+    Ok(args)
+}
+
+fn is_ident(text: &str) -> bool {
+    text.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && text.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{facet_fn, fn_shape};
+
+    /// Adds two numbers.
+    #[facet_fn(register = false)]
+    fn plus(x: i32, y: i32) -> i32 {
+        x + y
+    }
+
+    #[facet_fn(register = false)]
+    fn greet(name: &str, times: Option<usize>) -> Vec<String> {
+        vec![format!("hello {name}"); times.unwrap_or(1)]
+    }
+
+    #[facet_fn(register = false)]
+    fn initial(name: String, #[facet(default)] upper: bool) -> Option<char> {
+        let c = name.chars().next()?;
+        Some(if upper { c.to_ascii_uppercase() } else { c })
+    }
+
+    #[facet_fn(register = false)]
+    fn total(values: Vec<u32>) -> u32 {
+        values.iter().sum()
+    }
+
+    #[facet_fn(register = false)]
+    fn noop() {}
+
+    fn repl() -> Repl {
+        Repl::new(vec![
+            fn_shape!(plus),
+            fn_shape!(greet),
+            fn_shape!(initial),
+            fn_shape!(total),
+            fn_shape!(noop),
+        ])
+    }
+
+    #[test]
+    fn test_eval() {
+        let repl = repl();
+        assert_eq!(repl.eval("plus 2 3"), Ok("5".to_string()));
+        assert_eq!(repl.eval("  plus y=3 x=-2 "), Ok("1".to_string()));
+        assert_eq!(repl.eval("plus y=3 2"), Ok("5".to_string()));
+        assert_eq!(
+            repl.eval(r#"greet "Jane \"J\" Doe" Some(2)"#),
+            Ok(r#"["hello Jane \"J\" Doe", "hello Jane \"J\" Doe"]"#.to_string())
+        );
+        assert_eq!(
+            repl.eval("greet name='x=1'"),
+            Ok(r#"["hello x=1"]"#.to_string())
+        );
+        assert_eq!(repl.eval("initial bob true"), Ok("Some('B')".to_string()));
+        assert_eq!(repl.eval("initial '' false"), Ok("None".to_string()));
+        assert_eq!(repl.eval("initial bob"), Ok("Some('b')".to_string()));
+        assert_eq!(repl.eval("noop"), Ok(String::new()));
+        assert_eq!(repl.eval(""), Ok(String::new()));
+        noop();
+    }
+
+    #[test]
+    fn test_eval_errors() {
+        let repl = repl();
+        assert_eq!(
+            repl.eval("minus 1"),
+            Err(ReplError::UnknownFunction("minus".to_string()))
+        );
+        assert_eq!(
+            repl.eval("plus 1"),
+            Err(ReplError::MissingArg { name: "y" })
+        );
+        assert_eq!(
+            repl.eval("plus 1 2 3"),
+            Err(ReplError::TooManyArgs {
+                expected: 2,
+                got: 3
+            })
+        );
+        assert_eq!(
+            repl.eval("plus z=1"),
+            Err(ReplError::UnknownArg {
+                name: "z".to_string()
+            })
+        );
+        assert_eq!(
+            repl.eval("plus x=1 x=2"),
+            Err(ReplError::DuplicateArg { name: "x" })
+        );
+        assert_eq!(
+            repl.eval("plus 1 two"),
+            Err(ReplError::InvalidArg {
+                name: "y",
+                message: "invalid digit found in string".to_string()
+            })
+        );
+        assert_eq!(repl.eval("plus \"1"), Err(ReplError::UnterminatedQuote));
+        assert_eq!(
+            repl.eval("total 1"),
+            Err(ReplError::NotParseable {
+                name: "values",
+                type_name: "Vec<u32>".to_string()
+            })
+        );
+        assert_eq!(total(vec![1]), 1);
+    }
+
+    #[test]
+    fn test_completions() {
+        let repl = repl();
+        assert_eq!(repl.completions("p"), vec!["plus"]);
+        assert_eq!(repl.completions("l"), vec!["list"]);
+        assert_eq!(repl.completions("plus "), vec!["x=", "y="]);
+        assert_eq!(repl.completions("plus x=1 "), vec!["y="]);
+        assert_eq!(repl.completions("greet t"), vec!["times="]);
+        assert_eq!(repl.completions("plus x="), Vec::<String>::new());
+        assert_eq!(repl.completions("help g"), vec!["greet"]);
+        assert_eq!(repl.completions("minus "), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_builtins_shadowed() {
+        /// Lists nothing.
+        #[facet_fn(register = false)]
+        fn list() -> u8 {
+            0
+        }
+
+        #[facet_fn(register = false)]
+        fn help(topic: String) -> String {
+            format!("no help on {topic}")
+        }
+
+        // Functions named `help` or `list` win over the built-ins
+        let repl = Repl::new(vec![fn_shape!(plus), fn_shape!(list), fn_shape!(help)]);
+        assert_eq!(repl.eval("list"), Ok("0".to_string()));
+        assert_eq!(
+            repl.eval("help plus"),
+            Ok(r#""no help on plus""#.to_string())
+        );
+        assert_eq!(repl.completions("help "), vec!["topic="]);
+        assert_eq!(list(), 0);
+        assert_eq!(help("x".to_string()), "no help on x");
+    }
+
+    #[test]
+    fn test_run() {
+        let input = "help\nhelp plus\nplus 1 2\nplus 1\ngr\t\nplus \t\nnoop\nquit\nplus 1 1\n";
+        let mut output = Vec::new();
+        repl()
+            .run(input.as_bytes(), &mut output)
+            .expect("should run");
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "> plus(x: i32, y: i32) -> i32\n\
+             greet(name: &str, times: Option<usize>) -> Vec<String>\n\
+             initial(name: String, upper: bool) -> Option<char>\n\
+             total(values: Vec<u32>) -> u32\n\
+             noop()\n\
+             > plus(x: i32, y: i32) -> i32\n\nAdds two numbers.\n\
+             > 3\n\
+             > error: missing argument `y`\n\
+             > greet\n\
+             > x=  y=\n\
+             > > "
+        );
+
+        let mut output = Vec::new();
+        repl()
+            .run("plus 1 1".as_bytes(), &mut output)
+            .expect("should run");
+        assert_eq!(String::from_utf8(output).unwrap(), "> 2\n> \n");
+    }
+}
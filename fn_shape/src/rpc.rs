@@ -0,0 +1,391 @@
+//! A JSON-RPC 2.0 server over shaped functions.
+//!
+//! Requests are read one per line and answered one per line. The method is a function's
+//! qualified name, e.g. `add` or `Counter::new`, and `params` is an object or array as
+//! accepted by [`FunctionShape::call_json`]. The built-in `rpc.list` method returns every
+//! function's shape and JSON schema. It is also reachable as `list` unless a function has
+//! that name, in which case the function is called.
+
+use std::io::{self, BufRead, Write};
+
+use facet_value::{VArray, VObject, Value};
+
+use crate::{FunctionShape, JsonCallError};
+
+/// Invalid JSON was received
+pub const PARSE_ERROR: i64 = -32700;
+/// The JSON is not a valid request object
+pub const INVALID_REQUEST: i64 = -32600;
+/// No function has the requested name
+pub const METHOD_NOT_FOUND: i64 = -32601;
+/// The parameters do not match the function's parameters
+pub const INVALID_PARAMS: i64 = -32602;
+/// The call failed after its arguments were decoded
+pub const INTERNAL_ERROR: i64 = -32603;
+/// The function exists but cannot be called with JSON
+pub const NOT_CALLABLE: i64 = -32000;
+
+/// Dispatches JSON-RPC requests to a set of shapes
+pub struct Server {
+    shapes: Vec<FunctionShape>,
+}
+
+impl Server {
+    /// A server for `shapes`; methods are looked up by qualified name
+    pub fn new(shapes: Vec<FunctionShape>) -> Self {
+        Server { shapes }
+    }
+
+    /// A server for every shape in the global registry
+    #[cfg(feature = "registry")]
+    pub fn from_registry() -> Self {
+        Server::new(crate::registry::all())
+    }
+
+    /// Answer requests from `reader` on `writer` until end of input. Blank lines are skipped
+    /// and valid notifications, which have no `id`, get no response.
+    pub fn serve(&self, reader: impl BufRead, mut writer: impl Write) -> io::Result<()> {
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = self.handle(&line) {
+                writeln!(writer, "{response}")?;
+                writer.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The response to one request or batch, `None` when nothing needs answering
+    pub fn handle(&self, request: &str) -> Option<String> {
+        let response = match facet_json::from_str::<Value>(request) {
+            Ok(request) => match request.as_array() {
+                Some(batch) if batch.is_empty() => Some(error_response(
+                    Value::NULL,
+                    INVALID_REQUEST,
+                    "empty batch".to_string(),
+                )),
+                Some(batch) => {
+                    let responses: VArray = batch
+                        .iter()
+                        .filter_map(|req| self.handle_value(req))
+                        .collect();
+                    (!responses.is_empty()).then(|| responses.into())
+                }
+                None => self.handle_value(&request),
+            },
+            Err(err) => Some(error_response(Value::NULL, PARSE_ERROR, err.to_string())),
+        };
+        response
+            .map(|response| facet_json::to_string(&response).expect("JSON values always serialize"))
+    }
+
+    fn handle_value(&self, request: &Value) -> Option<Value> {
+        let Some(request) = request.as_object() else {
+            return Some(error_response(
+                Value::NULL,
+                INVALID_REQUEST,
+                "request must be an object".to_string(),
+            ));
+        };
+        let id = request.get("id").cloned();
+        let method = request
+            .get("method")
+            .and_then(Value::as_string)
+            .map(|method| method.as_str());
+        let version = request.get("jsonrpc").and_then(Value::as_string);
+        let (Some(version), Some(method)) = (version, method) else {
+            return Some(invalid_request(id));
+        };
+        if version.as_str() != "2.0" {
+            return Some(invalid_request(id));
+        }
+        let result = self.dispatch(method, request.get("params"));
+        // Only well-formed requests without an `id` are notifications
+        let id = id?;
+        Some(match result {
+            Ok(result) => {
+                let mut response = VObject::new();
+                response.insert("jsonrpc", "2.0");
+                response.insert("id", id);
+                response.insert("result", result);
+                response.into()
+            }
+            Err((code, message)) => error_response(id, code, message),
+        })
+    }
+
+    fn dispatch(&self, method: &str, params: Option<&Value>) -> Result<Value, (i64, String)> {
+        let shape = self
+            .shapes
+            .iter()
+            .find(|shape| shape.qualified_name() == method);
+        let shape = match shape {
+            Some(shape) => shape,
+            None if matches!(method, "rpc.list" | "list") => {
+                return Ok(self.shapes.iter().map(describe).collect::<VArray>().into());
+            }
+            None => return Err((METHOD_NOT_FOUND, format!("unknown method `{method}`"))),
+        };
+        let params = params.cloned().unwrap_or_else(|| VArray::new().into());
+        shape.call_json_value(params).map_err(|err| {
+            let code = match &err {
+                JsonCallError::NotCallable { .. }
+                | JsonCallError::NotDecodable { .. }
+                | JsonCallError::NotEncodable { .. } => NOT_CALLABLE,
+                JsonCallError::InvalidJson(_)
+                | JsonCallError::NotArguments
+                | JsonCallError::MissingArg { .. }
+                | JsonCallError::UnknownArg { .. }
+                | JsonCallError::TooManyArgs { .. }
+                | JsonCallError::ArgType { .. } => INVALID_PARAMS,
+                JsonCallError::Call(_) | JsonCallError::Encode(_) => INTERNAL_ERROR,
+            };
+            (code, err.to_string())
+        })
+    }
+}
+
+/// A shape as listed by the `list` method
+fn describe(shape: &FunctionShape) -> Value {
+    let params: VArray = shape
+        .params
+        .iter()
+        .map(|param| {
+            let mut described = VObject::new();
+            described.insert("name", param.name);
+            described.insert("type", param.type_name);
+            Value::from(described)
+        })
+        .collect();
+
+    let mut described = VObject::new();
+    described.insert("name", shape.qualified_name());
+    described.insert("doc", shape.doc);
+    described.insert("params", params);
+    described.insert("returns", shape.ret.type_name);
+    described.insert("callable", shape.is_json_callable());
+    described.insert("schema", shape.json_schema_value());
+    described.into()
+}
+
+/// The error for a request without a `jsonrpc: "2.0"` version or a `method` string, answered
+/// with `id: null` when the request has no usable `id`
+fn invalid_request(id: Option<Value>) -> Value {
+    error_response(
+        id.unwrap_or(Value::NULL),
+        INVALID_REQUEST,
+        "expected `jsonrpc: \"2.0\"` and a `method` string".to_string(),
+    )
+}
+
+fn error_response(id: Value, code: i64, message: String) -> Value {
+    let mut error = VObject::new();
+    error.insert("code", code);
+    error.insert("message", message);
+    let mut response = VObject::new();
+    response.insert("jsonrpc", "2.0");
+    response.insert("id", id);
+    response.insert("error", error);
+    response.into()
+}
+
+/// Serve every registered function on stdin and stdout
+#[cfg(feature = "registry")]
+pub fn serve_stdio() -> io::Result<()> {
+    Server::from_registry().serve(io::stdin().lock(), io::stdout().lock())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{facet_fn, fn_shape};
+
+    /// Adds two numbers.
+    #[facet_fn(register = false)]
+    fn plus(x: i32, y: i32) -> i32 {
+        x + y
+    }
+
+    #[facet_fn(register = false)]
+    fn first<T>(items: Vec<T>) -> Option<T> {
+        items.into_iter().next()
+    }
+
+    fn server() -> Server {
+        Server::new(vec![fn_shape!(plus), fn_shape!(first<u8>)])
+    }
+
+    #[test]
+    fn test_calls() {
+        let server = server();
+        assert_eq!(
+            server
+                .handle(
+                    r#"{"jsonrpc": "2.0", "id": 1, "method": "plus", "params": {"x": 2, "y": 3}}"#
+                )
+                .as_deref(),
+            Some(r#"{"jsonrpc":"2.0","id":1,"result":5}"#)
+        );
+        assert_eq!(
+            server
+                .handle(r#"{"jsonrpc": "2.0", "id": "a", "method": "plus", "params": [1, 1]}"#)
+                .as_deref(),
+            Some(r#"{"jsonrpc":"2.0","id":"a","result":2}"#)
+        );
+        // Notifications are called but not answered
+        assert_eq!(
+            server.handle(r#"{"jsonrpc": "2.0", "method": "plus", "params": [1, 1]}"#),
+            None
+        );
+        assert_eq!(
+            server
+                .handle(
+                    r#"[{"jsonrpc": "2.0", "id": 1, "method": "plus", "params": [1, 2]},
+                        {"jsonrpc": "2.0", "method": "plus", "params": [1, 2]}]"#
+                )
+                .as_deref(),
+            Some(r#"[{"jsonrpc":"2.0","id":1,"result":3}]"#)
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let server = server();
+        let code = |request: &str| {
+            let response = server.handle(request).expect("should respond");
+            let response: Value = facet_json::from_str(&response).expect("should be JSON");
+            let error = response.as_object().unwrap().get("error").cloned();
+            let code = error.unwrap().as_object().unwrap().get("code").cloned();
+            code.unwrap().as_number().unwrap().to_i64().unwrap()
+        };
+        assert_eq!(code("{"), PARSE_ERROR);
+        assert_eq!(code("[]"), INVALID_REQUEST);
+        assert_eq!(
+            server.handle("[1]").as_deref(),
+            Some(
+                r#"[{"jsonrpc":"2.0","id":null,"error":{"code":-32600,"message":"request must be an object"}}]"#
+            )
+        );
+        assert_eq!(code(r#"{"id": 1, "method": "plus"}"#), INVALID_REQUEST);
+        // Invalid requests are answered even without an `id`
+        assert_eq!(
+            server.handle(r#"{"method": 1}"#).as_deref(),
+            Some(
+                r#"{"jsonrpc":"2.0","id":null,"error":{"code":-32600,"message":"expected `jsonrpc: \"2.0\"` and a `method` string"}}"#
+            )
+        );
+        assert_eq!(
+            code(r#"{"jsonrpc": "2.0", "id": 1, "method": "minus"}"#),
+            METHOD_NOT_FOUND
+        );
+        assert_eq!(
+            code(r#"{"jsonrpc": "2.0", "id": 1, "method": "plus", "params": [1]}"#),
+            INVALID_PARAMS
+        );
+        assert_eq!(
+            code(r#"{"jsonrpc": "2.0", "id": 1, "method": "first", "params": [[1]]}"#),
+            NOT_CALLABLE
+        );
+        assert_eq!(
+            server
+                .handle(r#"{"jsonrpc": "2.0", "id": 7, "method": "plus", "params": {"x": 1}}"#)
+                .as_deref(),
+            Some(
+                r#"{"jsonrpc":"2.0","id":7,"error":{"code":-32602,"message":"missing argument `y`"}}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_list() {
+        let response = server()
+            .handle(r#"{"jsonrpc": "2.0", "id": 1, "method": "list"}"#)
+            .expect("should respond");
+        let response: Value = facet_json::from_str(&response).expect("should be JSON");
+        let result = response
+            .as_object()
+            .unwrap()
+            .get("result")
+            .cloned()
+            .unwrap();
+        let functions = result.as_array().unwrap();
+        assert_eq!(functions.len(), 2);
+
+        let plus = functions.get(0).unwrap().as_object().unwrap();
+        assert_eq!(
+            plus.get("name").unwrap().as_string().unwrap().as_str(),
+            "plus"
+        );
+        assert_eq!(
+            plus.get("doc").unwrap().as_string().unwrap().as_str(),
+            "Adds two numbers."
+        );
+        assert_eq!(plus.get("callable"), Some(&Value::from(true)));
+        assert_eq!(plus.get("params").unwrap().as_array().unwrap().len(), 2);
+        let schema = plus.get("schema").unwrap().as_object().unwrap();
+        assert_eq!(
+            schema
+                .get("description")
+                .unwrap()
+                .as_string()
+                .unwrap()
+                .as_str(),
+            "Adds two numbers."
+        );
+
+        let first = functions.get(1).unwrap().as_object().unwrap();
+        assert_eq!(first.get("callable"), Some(&Value::from(false)));
+    }
+
+    #[test]
+    fn test_list_shadowed() {
+        /// Lists nothing.
+        #[facet_fn(register = false)]
+        fn list() -> u8 {
+            0
+        }
+
+        let server = Server::new(vec![fn_shape!(plus), fn_shape!(list)]);
+        // A function named `list` wins over the built-in, which stays reachable as `rpc.list`
+        assert_eq!(
+            server
+                .handle(r#"{"jsonrpc": "2.0", "id": 1, "method": "list"}"#)
+                .as_deref(),
+            Some(r#"{"jsonrpc":"2.0","id":1,"result":0}"#)
+        );
+        let response = server
+            .handle(r#"{"jsonrpc": "2.0", "id": 1, "method": "rpc.list"}"#)
+            .expect("should respond");
+        let response: Value = facet_json::from_str(&response).expect("should be JSON");
+        let result = response.as_object().unwrap().get("result").cloned();
+        assert_eq!(result.unwrap().as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_serve() {
+        let input = concat!(
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "plus", "params": [1, 2]}"#,
+            "\n\n",
+            r#"{"jsonrpc": "2.0", "method": "plus", "params": [1, 2]}"#,
+            "\n",
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "plus", "params": [3, 4]}"#,
+            "\n",
+        );
+        let mut output = Vec::new();
+        server()
+            .serve(input.as_bytes(), &mut output)
+            .expect("should serve");
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            concat!(
+                r#"{"jsonrpc":"2.0","id":1,"result":3}"#,
+                "\n",
+                r#"{"jsonrpc":"2.0","id":2,"result":7}"#,
+                "\n",
+            )
+        );
+    }
+}
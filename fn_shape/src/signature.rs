@@ -0,0 +1,309 @@
+//! Human-readable signatures.
+//!
+//! `{}` renders a shape as the function was declared, e.g.
+//! `fn generic_add<T: Add<Output = T>>(x: T, y: T) -> T`, and `{:#}` renders the
+//! instantiation, e.g. `generic_add::<i32>(x: i32, y: i32) -> i32`. The macros record types
+//! spaced token by token, as in `Vec < & 'a str >`; they are tidied up here.
+
+use core::fmt;
+
+use crate::{FunctionShape, Receiver};
+
+impl FunctionShape {
+    /// The signature as declared, with visibility, qualifiers, generics and where clause
+    pub fn signature(&self) -> String {
+        let qualifiers = &self.qualifiers;
+        let mut signature = String::new();
+        for (present, word) in [
+            (!qualifiers.visibility.is_empty(), qualifiers.visibility),
+            (qualifiers.is_const, "const"),
+            (qualifiers.is_async, "async"),
+            (qualifiers.is_unsafe, "unsafe"),
+        ] {
+            if present {
+                signature.push_str(word);
+                signature.push(' ');
+            }
+        }
+        if let Some(abi) = qualifiers.abi {
+            signature.push_str(&format!("extern \"{abi}\" "));
+        }
+
+        let params = self
+            .params
+            .iter()
+            .map(|param| format!("{}: {}", tidy(param.ident), tidy(param.type_name)));
+        signature.push_str(&format!(
+            "fn {}{}({})",
+            self.ident,
+            tidy(self.generics),
+            self.receiver_text()
+                .into_iter()
+                .chain(params)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+        if !self.ret.is_unit {
+            signature.push_str(" -> ");
+            signature.push_str(&tidy(self.ret.type_name));
+        }
+        if !self.where_predicates.is_empty() {
+            let predicates: Vec<_> = self.where_predicates.iter().map(|p| tidy(p)).collect();
+            signature.push_str(" where ");
+            signature.push_str(&predicates.join(", "));
+        }
+        signature
+    }
+
+    /// The signature of this instantiation: the generic arguments in a turbofish and the
+    /// resolved types, without their module paths
+    pub fn instantiated_signature(&self) -> String {
+        let mut signature = self.ident.to_string();
+        if !self.generic_args.is_empty() {
+            let args: Vec<_> = self
+                .generic_args
+                .iter()
+                .map(|arg| short_type_name(&arg.get()))
+                .collect();
+            signature.push_str(&format!("::<{}>", args.join(", ")));
+        }
+        let params = self.params.iter().map(|param| {
+            let ty = match param.rust_type_name() {
+                Some(name) => short_type_name(name),
+                None => tidy(param.type_name),
+            };
+            format!("{}: {ty}", tidy(param.ident))
+        });
+        let params: Vec<_> = self.receiver_text().into_iter().chain(params).collect();
+        signature.push_str(&format!("({})", params.join(", ")));
+        if !self.ret.is_unit {
+            let ret = match self.ret.rust_type_name() {
+                Some(name) => short_type_name(name),
+                None => tidy(self.ret.type_name),
+            };
+            signature.push_str(" -> ");
+            signature.push_str(&ret);
+        }
+        signature
+    }
+
+    /// The receiver as written, e.g. `&mut self`
+    fn receiver_text(&self) -> Option<String> {
+        self.receiver.map(|receiver| match receiver {
+            Receiver::Value => "self".to_string(),
+            Receiver::Ref => "&self".to_string(),
+            Receiver::RefMut => "&mut self".to_string(),
+            Receiver::Typed(ty) => format!("self: {}", tidy(ty)),
+        })
+    }
+}
+
+/// `{}` is [`FunctionShape::signature`] and `{:#}` is
+/// [`FunctionShape::instantiated_signature`]
+impl fmt::Display for FunctionShape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            f.write_str(&self.instantiated_signature())
+        } else {
+            f.write_str(&self.signature())
+        }
+    }
+}
+
+/// Token-spaced text such as `Vec < & 'a str >` with the usual spacing: `Vec<&'a str>`
+pub(crate) fn tidy(text: &str) -> String {
+    let mut tidied = String::new();
+    let mut prev: Option<&str> = None;
+    for token in tokens(text) {
+        if prev.is_some_and(|prev| spaced(prev, token)) {
+            tidied.push(' ');
+        }
+        tidied.push_str(token);
+        prev = Some(token);
+    }
+    tidied
+}
+
+/// The tokens of `text`: words and lifetimes, `::`, `->`, or single characters
+fn tokens(text: &str) -> Vec<&str> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let mut tokens = Vec::new();
+    let mut rest = text.trim_start();
+    while let Some(first) = rest.chars().next() {
+        let len = if is_word(first) || first == '\'' {
+            let tail = &rest[first.len_utf8()..];
+            first.len_utf8() + tail.find(|c| !is_word(c)).unwrap_or(tail.len())
+        } else if rest.starts_with("::") || rest.starts_with("->") {
+            2
+        } else {
+            first.len_utf8()
+        };
+        tokens.push(&rest[..len]);
+        rest = rest[len..].trim_start();
+    }
+    tokens
+}
+
+/// Whether a space goes between two adjacent tokens
+fn spaced(prev: &str, next: &str) -> bool {
+    let is_word = prev.starts_with(|c: char| c.is_alphanumeric() || c == '_');
+    match (prev, next) {
+        (_, ">" | "," | ")" | "]" | ";" | ":" | "::" | ".") => false,
+        ("<" | "(" | "[" | "&" | "*" | "?" | "::" | ".", _) => false,
+        // Generic arguments and `Fn(..)` follow their path directly
+        (_, "<" | "(") => !is_word && prev != ">",
+        _ => true,
+    }
+}
+
+/// A name from `core::any::type_name` without module paths, e.g. `Vec<String>` for
+/// `alloc::vec::Vec<alloc::string::String>`
+fn short_type_name(name: &str) -> String {
+    let mut short = String::new();
+    let mut rest = name;
+    while let Some(c) = rest.chars().next() {
+        if rest.starts_with("::") {
+            let path_len = short
+                .trim_end_matches(|c: char| c.is_alphanumeric() || c == '_')
+                .len();
+            short.truncate(path_len);
+            rest = &rest[2..];
+        } else {
+            short.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    short
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{facet_fn, facet_impl, fn_shape};
+    use core::ops::Add;
+
+    #[facet_fn]
+    fn generic_add<T: Add<Output = T>>(x: T, y: T) -> T {
+        x + y
+    }
+
+    /// Fills `out` with copies of `item`.
+    #[facet_fn]
+    pub(crate) fn fill<'a, T, const N: usize>(out: &'a mut [T; N], item: &T) -> &'a [T]
+    where
+        T: Clone + 'a,
+    {
+        out.fill(item.clone());
+        out
+    }
+
+    #[facet_fn]
+    unsafe extern "C" fn first(ptr: *const u8) -> u8 {
+        unsafe { *ptr }
+    }
+
+    #[facet_fn]
+    fn apply(f: impl Fn(u8) -> Vec<u8>, (a, b): (u8, u8)) -> usize {
+        f(a).len() + usize::from(b)
+    }
+
+    struct Meter(u32);
+
+    #[facet_impl]
+    impl Meter {
+        fn read(&self) -> u32 {
+            self.0
+        }
+
+        #[allow(clippy::boxed_local)]
+        fn reset(self: Box<Self>, value: Option<u32>) {
+            let _ = value;
+        }
+    }
+
+    #[test]
+    fn test_signature() {
+        let shape = fn_shape!(generic_add<i32>);
+        assert_eq!(
+            shape.to_string(),
+            "fn generic_add<T: Add<Output = T>>(x: T, y: T) -> T"
+        );
+        assert_eq!(
+            format!("{shape:#}"),
+            "generic_add::<i32>(x: i32, y: i32) -> i32"
+        );
+        assert_eq!(generic_add(2, 3), 5);
+
+        let shape = fn_shape!(fill<String, 2>);
+        assert_eq!(
+            shape.signature(),
+            "pub(crate) fn fill<'a, T, const N: usize>(out: &'a mut [T; N], item: &T) -> &'a [T] \
+             where T: Clone + 'a"
+        );
+        assert_eq!(
+            shape.instantiated_signature(),
+            "fill::<String, 2>(out: &mut [String; 2], item: &String) -> &[String]"
+        );
+        assert_eq!(fill(&mut [0, 0], &1), &[1, 1]);
+
+        assert_eq!(
+            fn_shape!(first).to_string(),
+            "unsafe extern \"C\" fn first(ptr: *const u8) -> u8"
+        );
+        assert_eq!(unsafe { first(&7) }, 7);
+
+        // Types that cannot be named are shown as written
+        let shape = fn_shape!(apply);
+        assert_eq!(
+            shape.to_string(),
+            "fn apply(f: impl Fn(u8) -> Vec<u8>, (a, b): (u8, u8)) -> usize"
+        );
+        assert_eq!(
+            format!("{shape:#}"),
+            "apply(f: impl Fn(u8) -> Vec<u8>, (a, b): (u8, u8)) -> usize"
+        );
+        assert_eq!(apply(|x| vec![x], (1, 2)), 3);
+    }
+
+    #[test]
+    fn test_method_signature() {
+        assert_eq!(fn_shape!(Meter::read).to_string(), "fn read(&self) -> u32");
+        let shape = fn_shape!(Meter::reset);
+        assert_eq!(
+            shape.to_string(),
+            "fn reset(self: Box<Self>, value: Option<u32>)"
+        );
+        assert_eq!(
+            format!("{shape:#}"),
+            "reset(self: Box<Self>, value: Option<u32>)"
+        );
+        Box::new(Meter(1)).reset(None);
+        assert_eq!(Meter(2).read(), 2);
+    }
+
+    #[test]
+    fn test_tidy() {
+        assert_eq!(tidy("Vec < Vec < & 'a str > >"), "Vec<Vec<&'a str>>");
+        assert_eq!(tidy("< I as Iterator > :: Item"), "<I as Iterator>::Item");
+        assert_eq!(
+            tidy("& mut dyn FnMut (u8 , u8) -> (u8, u8)"),
+            "&mut dyn FnMut(u8, u8) -> (u8, u8)"
+        );
+        assert_eq!(tidy("Vec :: < u8 >"), "Vec::<u8>");
+        assert_eq!(tidy("T : 'a + ? Sized"), "T: 'a + ?Sized");
+        assert_eq!(tidy(""), "");
+    }
+
+    #[test]
+    fn test_short_type_name() {
+        assert_eq!(
+            short_type_name("alloc::vec::Vec<alloc::string::String>"),
+            "Vec<String>"
+        );
+        assert_eq!(
+            short_type_name("core::option::Option<&[fn_shape::Meter; 2]>"),
+            "Option<&[Meter; 2]>"
+        );
+        assert_eq!(short_type_name("(u8, &str)"), "(u8, &str)");
+    }
+}
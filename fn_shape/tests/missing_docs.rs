@@ -0,0 +1,62 @@
+//! Generated items are documented, so crates denying `missing_docs` can use the macros.
+#![deny(missing_docs)]
+
+use fn_shape::{facet_fn, facet_impl, fn_shape};
+
+/// Adds two numbers.
+#[facet_fn(skip_shape)]
+pub fn add(x: i32, y: i32) -> i32 {
+    x + y
+}
+
+/// Parses `text`, if it can be.
+#[facet_fn(skip_shape)]
+pub fn parse<T: core::str::FromStr>(text: &str) -> Option<T> {
+    text.parse().ok()
+}
+
+/// Doubles a number.
+#[facet_fn(register = false)]
+pub fn double(x: i32) -> i32 {
+    x * 2
+}
+
+/// Picks the item at `index`.
+#[facet_fn(register = false)]
+pub fn pick<T, const N: usize>(items: &[T; N], index: usize) -> Option<&T> {
+    items.get(index)
+}
+
+/// A value to convert.
+pub struct Wrapper<T>(pub T);
+
+#[facet_impl]
+impl<T: Clone> Wrapper<T> {
+    /// The value as another type.
+    #[facet_fn(register = false)]
+    pub fn convert<U: From<T>>(&self) -> U {
+        U::from(self.0.clone())
+    }
+
+    /// The value.
+    #[facet_fn(register = false)]
+    pub fn get(&self) -> T {
+        self.0.clone()
+    }
+}
+
+#[test]
+fn test_documented() {
+    let args = AddArgs::builder().with_x(1).with_y(2).build();
+    assert_eq!(args.map(AddArgs::call), Ok(3));
+    assert_eq!(add(2, 2), 4);
+    let args = ParseArgs::<u8>::builder().with_text("7").build();
+    assert_eq!(args.map(ParseArgs::call), Ok(Some(7)));
+
+    assert_eq!(fn_shape!(double).name, "double");
+    assert_eq!(fn_shape!(pick<u8, 2>).name, "pick");
+    assert_eq!(fn_shape!(Wrapper::<u8>::convert<u16>).name, "convert");
+    assert_eq!(fn_shape!(Wrapper::<u8>::get).name, "get");
+    assert_eq!((double(1), pick(&[1], 0)), (2, Some(&1)));
+    assert_eq!((Wrapper(1u8).convert::<u16>(), Wrapper(1u8).get()), (1, 1));
+}
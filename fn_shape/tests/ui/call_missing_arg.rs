@@ -0,0 +1,10 @@
+use fn_shape::{call, facet_fn};
+
+#[facet_fn]
+fn add(x: i32, y: i32) -> i32 {
+    x + y
+}
+
+fn main() {
+    call!(add, y = 2);
+}
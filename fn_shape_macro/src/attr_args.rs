@@ -0,0 +1,515 @@
+use proc_macro2::TokenStream;
+use unsynn::*;
+
+// Re-use the error helpers and the angle-bracket-aware token parser
+use crate::error::{ParseError, Result, expect_end};
+use crate::generics::VerbatimUntil;
+
+unsynn! {
+    /// A single attribute argument: `name` or `name = value`
+    pub struct AttrArg {
+        /// Argument name
+        pub name: Ident,
+        /// Optional `= value`
+        pub value: Option<Cons<Assign, TokenTree>>,
+    }
+
+    /// A parameter attribute argument: `name` or `name = expression`
+    pub struct ParamArg {
+        /// Argument name
+        pub name: Ident,
+        /// Optional `= expression`; the expression is everything until the next comma
+        pub value: Option<Cons<Assign, VerbatimUntil<Comma>>>,
+    }
+}
+
+/// The item whose attribute arguments are parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    /// `#[facet_fn]` on a free function
+    Function,
+    /// `#[facet_impl]` on an impl block
+    Impl,
+    /// `#[facet_fn]` on a method inside a `#[facet_impl]` block
+    Method,
+}
+
+impl Target {
+    /// The attribute as written, for error messages
+    fn attr(self) -> &'static str {
+        match self {
+            Target::Function | Target::Method => "facet_fn",
+            Target::Impl => "facet_impl",
+        }
+    }
+}
+
+/// Casing applied to the exported parameter names by `rename_all`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenameRule {
+    /// `snake_case`: the names as written
+    Snake,
+    /// `camelCase`
+    Camel,
+    /// `kebab-case`
+    Kebab,
+}
+
+impl RenameRule {
+    /// Rename a snake_case identifier; leading underscores are kept
+    pub fn apply(self, name: &str) -> String {
+        let name = name.trim_start_matches("r#");
+        let rest = name.trim_start_matches('_');
+        let prefix = &name[..name.len() - rest.len()];
+        let renamed = match self {
+            RenameRule::Snake => rest.to_string(),
+            RenameRule::Kebab => rest.replace('_', "-"),
+            RenameRule::Camel => rest
+                .split('_')
+                .filter(|word| !word.is_empty())
+                .enumerate()
+                .map(|(index, word)| {
+                    let mut chars = word.chars();
+                    match chars.next() {
+                        Some(first) if index > 0 => first.to_uppercase().chain(chars).collect(),
+                        _ => word.to_string(),
+                    }
+                })
+                .collect(),
+        };
+        format!("{prefix}{renamed}")
+    }
+}
+
+/// Arguments of `#[facet_fn(...)]` and `#[facet_impl(...)]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShapeArgs {
+    /// Whether to add the shape to the global registry (`register = false` or
+    /// `skip_registry` opts out)
+    pub register: bool,
+    /// The name recorded in the shape, from `name = "..."`, in place of the Rust identifier
+    pub name: Option<String>,
+    /// Casing of the exported parameter names, from `rename_all = "..."`
+    pub rename_all: Option<RenameRule>,
+    /// Tags from `tags = ["...", ...]`
+    pub tags: Vec<String>,
+    /// Whether to generate the shape constant at all (`skip_shape` opts out)
+    pub shape: bool,
+    /// Whether to move the function into a hidden module behind a wrapper (`skip_module`
+    /// keeps it as written and puts the shape constant beside it)
+    pub module: bool,
+    /// Whether to re-export the shape constant as `FOO_SHAPE` (`skip_export` opts out)
+    pub export: bool,
+    /// Whether to generate the `FooArgs` struct (`skip_args` opts out)
+    pub args_struct: bool,
+}
+
+impl Default for ShapeArgs {
+    fn default() -> Self {
+        ShapeArgs {
+            register: true,
+            name: None,
+            rename_all: None,
+            tags: Vec::new(),
+            shape: true,
+            module: true,
+            export: true,
+            args_struct: true,
+        }
+    }
+}
+
+impl ShapeArgs {
+    /// Apply the arguments in `args` on top of `self`, so that a method's marker can
+    /// override the arguments of its impl block. Arguments that do not apply to `target`,
+    /// such as `name` on an impl block, are errors.
+    pub fn parse(mut self, args: TokenStream, target: Target) -> Result<Self> {
+        let attr = target.attr();
+        let what = format!("#[{attr}] arguments");
+        let mut it = args.to_token_iter();
+        let parsed = it
+            .parse::<CommaDelimitedVec<AttrArg>>()
+            .map_err(|err| ParseError::from_unsynn(&err, &args, &what))?;
+        expect_end(&mut it, &what)?;
+
+        for arg in parsed.0.into_iter().map(|delimited| delimited.value) {
+            let name = arg.name.to_string();
+            let unsupported = match name.as_str() {
+                "name" => target == Target::Impl,
+                "skip_shape" | "skip_module" | "skip_export" | "skip_args" => {
+                    target != Target::Function
+                }
+                _ => false,
+            };
+            if unsupported {
+                let on = match target {
+                    Target::Impl => "impl blocks",
+                    _ => "methods",
+                };
+                return Err(ParseError::new(
+                    arg.name.span(),
+                    format!("#[{attr}] argument `{name}` is not supported on {on}"),
+                ));
+            }
+            match name.as_str() {
+                "register" => self.register = bool_value(&arg)?,
+                "skip_registry" => self.register = !flag(&arg)?,
+                "name" => self.name = Some(string_value(&arg)?),
+                "rename_all" => {
+                    let rule = string_value(&arg)?;
+                    self.rename_all = Some(match rule.as_str() {
+                        "snake_case" => RenameRule::Snake,
+                        "camelCase" => RenameRule::Camel,
+                        "kebab-case" => RenameRule::Kebab,
+                        _ => {
+                            return Err(ParseError::new(
+                                value_span(&arg),
+                                format!(
+                                    "unknown `rename_all` rule \"{rule}\"; expected \
+                                     \"snake_case\", \"camelCase\" or \"kebab-case\""
+                                ),
+                            ));
+                        }
+                    });
+                }
+                "tags" => self.tags = tags_value(&arg)?,
+                "skip_shape" => self.shape = !flag(&arg)?,
+                "skip_module" => self.module = !flag(&arg)?,
+                "skip_export" => self.export = !flag(&arg)?,
+                "skip_args" => self.args_struct = !flag(&arg)?,
+                other => {
+                    return Err(ParseError::new(
+                        arg.name.span(),
+                        format!("unknown #[{attr}] argument `{other}`"),
+                    ));
+                }
+            }
+        }
+        Ok(self)
+    }
+
+    /// The exported name of a parameter: `display_name` cased by `rename_all`, unless the
+    /// parameter is a pattern
+    pub fn param_name(&self, display_name: &str, is_pattern: bool) -> String {
+        match self.rename_all {
+            Some(rule) if !is_pattern => rule.apply(display_name),
+            _ => display_name.to_string(),
+        }
+    }
+}
+
+/// The default of a parameter with `#[facet(default)]` or `#[facet(default = expr)]`
+#[derive(Debug, Clone)]
+pub struct ParamDefault {
+    /// The expression producing the default
+    pub expr: TokenStream,
+    /// The expression as written, `Default::default()` for a bare `default`
+    pub source: String,
+}
+
+/// Arguments of `#[facet(...)]` on a parameter
+#[derive(Debug, Clone, Default)]
+pub struct ParamArgs {
+    pub default: Option<ParamDefault>,
+}
+
+impl ParamArgs {
+    /// Apply the arguments in `args` on top of `self`
+    pub fn parse(mut self, args: TokenStream) -> Result<Self> {
+        let what = "#[facet] parameter arguments";
+        let mut it = args.to_token_iter();
+        let parsed = it
+            .parse::<CommaDelimitedVec<ParamArg>>()
+            .map_err(|err| ParseError::from_unsynn(&err, &args, what))?;
+        expect_end(&mut it, what)?;
+
+        for arg in parsed.0.into_iter().map(|delimited| delimited.value) {
+            match arg.name.to_string().as_str() {
+                "default" => {
+                    self.default = Some(match arg.value {
+                        Some(value) => ParamDefault {
+                            expr: value.second.to_token_stream(),
+                            source: value.second.tokens_to_string(),
+                        },
+                        None => ParamDefault {
+                            expr: quote::quote! { ::core::default::Default::default() },
+                            source: "Default::default()".to_string(),
+                        },
+                    });
+                }
+                other => {
+                    return Err(ParseError::new(
+                        arg.name.span(),
+                        format!("unknown #[facet] parameter argument `{other}`"),
+                    ));
+                }
+            }
+        }
+        Ok(self)
+    }
+}
+
+/// The span of an argument's value, or of its name when it has none
+fn value_span(arg: &AttrArg) -> Span {
+    match &arg.value {
+        Some(value) => value.second.span(),
+        None => arg.name.span(),
+    }
+}
+
+/// A bare flag such as `skip_registry`, which takes no value
+fn flag(arg: &AttrArg) -> Result<bool> {
+    match &arg.value {
+        Some(value) => Err(ParseError::new(
+            value.second.span(),
+            format!("`{}` takes no value", arg.name),
+        )),
+        None => Ok(true),
+    }
+}
+
+/// The value of a `name = "..."` argument, without the quotes
+fn string_value(arg: &AttrArg) -> Result<String> {
+    let value = arg.value.as_ref().map(|value| &value.second);
+    let string = value.and_then(|value| {
+        let tokens = value.to_token_stream();
+        let mut it = tokens.to_token_iter();
+        it.parse::<Cons<LiteralString, EndOfStream>>().ok()
+    });
+    string
+        .map(|string| string.first.as_str().to_string())
+        .ok_or_else(|| {
+            ParseError::new(
+                value_span(arg),
+                format!("expected `{} = \"...\"`", arg.name),
+            )
+        })
+}
+
+/// The value of a `tags = ["...", ...]` argument
+fn tags_value(arg: &AttrArg) -> Result<Vec<String>> {
+    let error = || ParseError::new(value_span(arg), "expected `tags = [\"...\", ...]`");
+    let Some(TokenTree::Group(group)) = arg.value.as_ref().map(|value| &value.second) else {
+        return Err(error());
+    };
+    if group.delimiter() != Delimiter::Bracket {
+        return Err(error());
+    }
+    let stream = group.stream();
+    let mut it = stream.to_token_iter();
+    let tags = it
+        .parse::<Cons<CommaDelimitedVec<LiteralString>, EndOfStream>>()
+        .map_err(|_| error())?;
+    Ok(tags
+        .first
+        .0
+        .into_iter()
+        .map(|tag| tag.value.as_str().to_string())
+        .collect())
+}
+
+/// The value of a `name = true` or `name = false` argument
+fn bool_value(arg: &AttrArg) -> Result<bool> {
+    let name = &arg.name;
+    let error = |span| {
+        ParseError::new(
+            span,
+            format!("expected `{name} = true` or `{name} = false`"),
+        )
+    };
+    match &arg.value {
+        Some(value) => match &value.second {
+            TokenTree::Ident(ident) if ident == "true" => Ok(true),
+            TokenTree::Ident(ident) if ident == "false" => Ok(false),
+            other => Err(error(other.span())),
+        },
+        None => Err(error(name.span())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    #[test]
+    fn test_no_arguments() {
+        let args = ShapeArgs::default()
+            .parse(quote! {}, Target::Function)
+            .expect("should parse");
+        assert_eq!(args, ShapeArgs::default());
+        assert!(args.register);
+    }
+
+    #[test]
+    fn test_register() {
+        let args = ShapeArgs::default()
+            .parse(quote! { register = false }, Target::Function)
+            .expect("should parse");
+        assert!(!args.register);
+
+        // Later arguments, e.g. from a method marker, override earlier ones
+        let args = args
+            .parse(quote! { register = true, }, Target::Function)
+            .expect("should parse");
+        assert!(args.register);
+    }
+
+    #[test]
+    fn test_invalid_arguments() {
+        let err = ShapeArgs::default()
+            .parse(quote! { rename = "x" }, Target::Function)
+            .expect_err("should fail");
+        assert_eq!(err.message, "unknown #[facet_fn] argument `rename`");
+
+        let err = ShapeArgs::default()
+            .parse(quote! { register }, Target::Impl)
+            .expect_err("should fail");
+        assert_eq!(
+            err.message,
+            "expected `register = true` or `register = false`"
+        );
+
+        let err = ShapeArgs::default()
+            .parse(quote! { register = 1 }, Target::Function)
+            .expect_err("should fail");
+        assert_eq!(
+            err.message,
+            "expected `register = true` or `register = false`"
+        );
+
+        assert!(
+            ShapeArgs::default()
+                .parse(quote! { register = true false }, Target::Function)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_names_and_tags() {
+        let args = ShapeArgs::default()
+            .parse(
+                quote! { name = "sum", rename_all = "camelCase", tags = ["math", "pure"] },
+                Target::Function,
+            )
+            .expect("should parse");
+        assert_eq!(args.name.as_deref(), Some("sum"));
+        assert_eq!(args.rename_all, Some(RenameRule::Camel));
+        assert_eq!(args.tags, vec!["math", "pure"]);
+        assert_eq!(args.param_name("max_len", false), "maxLen");
+        assert_eq!(args.param_name("(a, b)", true), "(a, b)");
+
+        let args = ShapeArgs::default()
+            .parse(quote! { tags = [] }, Target::Impl)
+            .expect("should parse");
+        assert!(args.tags.is_empty());
+        assert_eq!(args.param_name("max_len", false), "max_len");
+    }
+
+    #[test]
+    fn test_rename_rules() {
+        assert_eq!(RenameRule::Camel.apply("max_retry_count"), "maxRetryCount");
+        assert_eq!(RenameRule::Camel.apply("_1"), "_1");
+        assert_eq!(RenameRule::Camel.apply("__private_name"), "__privateName");
+        assert_eq!(RenameRule::Kebab.apply("max_len"), "max-len");
+        assert_eq!(RenameRule::Kebab.apply("r#type"), "type");
+        assert_eq!(RenameRule::Snake.apply("max_len"), "max_len");
+    }
+
+    #[test]
+    fn test_skip_flags() {
+        let args = ShapeArgs::default()
+            .parse(
+                quote! { skip_registry, skip_export, skip_module, skip_args },
+                Target::Function,
+            )
+            .expect("should parse");
+        assert!(!args.register && !args.export && !args.module && !args.args_struct);
+        assert!(args.shape);
+
+        let args = ShapeArgs::default()
+            .parse(quote! { skip_shape }, Target::Function)
+            .expect("should parse");
+        assert!(!args.shape);
+
+        let args = ShapeArgs::default()
+            .parse(quote! { skip_registry }, Target::Method)
+            .expect("should parse");
+        assert!(!args.register);
+    }
+
+    #[test]
+    fn test_unsupported_arguments() {
+        let err = ShapeArgs::default()
+            .parse(quote! { name = "x" }, Target::Impl)
+            .expect_err("should fail");
+        assert_eq!(
+            err.message,
+            "#[facet_impl] argument `name` is not supported on impl blocks"
+        );
+
+        let err = ShapeArgs::default()
+            .parse(quote! { skip_module }, Target::Method)
+            .expect_err("should fail");
+        assert_eq!(
+            err.message,
+            "#[facet_fn] argument `skip_module` is not supported on methods"
+        );
+
+        let err = ShapeArgs::default()
+            .parse(quote! { skip_export = true }, Target::Function)
+            .expect_err("should fail");
+        assert_eq!(err.message, "`skip_export` takes no value");
+
+        let err = ShapeArgs::default()
+            .parse(quote! { name = sum }, Target::Function)
+            .expect_err("should fail");
+        assert_eq!(err.message, "expected `name = \"...\"`");
+
+        let err = ShapeArgs::default()
+            .parse(quote! { rename_all = "PascalCase" }, Target::Function)
+            .expect_err("should fail");
+        assert!(
+            err.message
+                .starts_with("unknown `rename_all` rule \"PascalCase\"")
+        );
+
+        for tags in [
+            quote! { tags = "math" },
+            quote! { tags = [math] },
+            quote! { tags },
+        ] {
+            let err = ShapeArgs::default()
+                .parse(tags, Target::Function)
+                .expect_err("should fail");
+            assert_eq!(err.message, "expected `tags = [\"...\", ...]`");
+        }
+    }
+
+    #[test]
+    fn test_param_defaults() {
+        let args = ParamArgs::default()
+            .parse(quote! { default })
+            .expect("should parse");
+        let default = args.default.expect("should have a default");
+        assert_eq!(default.source, "Default::default()");
+        assert_eq!(
+            default.expr.to_string(),
+            ":: core :: default :: Default :: default ()"
+        );
+
+        let args = ParamArgs::default()
+            .parse(quote! { default = Vec::<u8, Global>::with_capacity(4) })
+            .expect("should parse");
+        assert_eq!(
+            args.default.expect("should have a default").source,
+            "Vec :: < u8 , Global > :: with_capacity (4)"
+        );
+
+        let err = ParamArgs::default()
+            .parse(quote! { rename = "x" })
+            .expect_err("should fail");
+        assert_eq!(err.message, "unknown #[facet] parameter argument `rename`");
+        assert!(ParamArgs::default().parse(quote! { default = }).is_err());
+    }
+}
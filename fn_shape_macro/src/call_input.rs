@@ -0,0 +1,180 @@
+use proc_macro2::TokenStream;
+use unsynn::*;
+
+// Re-use the angle-bracket-aware token parser and error helpers
+use crate::error::{ParseError, Result, expect_end};
+use crate::generics::VerbatimUntil;
+
+unsynn! {
+    /// A named argument of `call!`: `name = value`, or `name` for a variable of that name
+    pub struct NamedArg {
+        /// Parameter name
+        pub name: Ident,
+        /// Optional `= value`; the value is everything until the next comma
+        pub value: Option<Cons<Assign, VerbatimUntil<Comma>>>,
+    }
+
+    /// Input to `call!`: a function path followed by named arguments, e.g.
+    /// `math::add, y = 3, x = 2`
+    pub struct CallInput {
+        /// Optional leading `::`
+        pub leading_sep: Option<PathSep>,
+        /// Module path of the function, each segment followed by `::`
+        pub path: Any<Cons<Ident, PathSep>>,
+        /// Function name
+        pub name: Ident,
+        /// Named arguments after a comma
+        pub args: Option<Cons<Comma, CommaDelimitedVec<NamedArg>>>,
+    }
+
+    /// Input to `__call_checked!`: the `call!` input after the function's parameters, those
+    /// without a default in the first brackets and the others in the second, e.g.
+    /// `[x y] [] math::add, y = 3, x = 2`
+    pub struct CheckedCallInput {
+        /// Parameters that must be given
+        pub required: BracketGroupContaining<Any<Ident>>,
+        /// Parameters with a default
+        pub defaulted: BracketGroupContaining<Any<Ident>>,
+        /// The `call!` input
+        pub call: CallInput,
+    }
+}
+
+/// Parsed call! input
+pub struct ParsedCallInput {
+    /// Module path including the trailing `::`, empty for functions in scope
+    pub path: TokenStream,
+    pub name: Ident,
+    /// Arguments in the order written, with their value unless given as a shorthand
+    pub args: Vec<(Ident, Option<TokenStream>)>,
+}
+
+/// Parsed `__call_checked!` input
+pub struct ParsedCheckedCall {
+    pub required: Vec<Ident>,
+    pub defaulted: Vec<Ident>,
+    pub call: ParsedCallInput,
+}
+
+/// Parse call! macro input from TokenStream
+pub fn parse_call_input(input: TokenStream) -> Result<ParsedCallInput> {
+    let mut it = input.to_token_iter();
+
+    let call = it
+        .parse::<CallInput>()
+        .map_err(|err| ParseError::from_unsynn(&err, &input, "call! input"))?;
+    expect_end(&mut it, "call! input")?;
+    Ok(parsed_call(call))
+}
+
+/// Parse `__call_checked!` input from TokenStream
+pub fn parse_checked_call(input: TokenStream) -> Result<ParsedCheckedCall> {
+    let mut it = input.to_token_iter();
+
+    let checked = it
+        .parse::<CheckedCallInput>()
+        .map_err(|err| ParseError::from_unsynn(&err, &input, "call! input"))?;
+    expect_end(&mut it, "call! input")?;
+    Ok(ParsedCheckedCall {
+        required: idents(checked.required.content),
+        defaulted: idents(checked.defaulted.content),
+        call: parsed_call(checked.call),
+    })
+}
+
+fn idents(idents: Any<Ident>) -> Vec<Ident> {
+    idents
+        .0
+        .into_iter()
+        .map(|delimited| delimited.value)
+        .collect()
+}
+
+fn parsed_call(call: CallInput) -> ParsedCallInput {
+    let mut path = call.leading_sep.to_token_stream();
+    path.extend(call.path.to_token_stream());
+    let args = call
+        .args
+        .map(|args| args.second.0)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|delimited| {
+            let arg = delimited.value;
+            (
+                arg.name,
+                arg.value.map(|value| value.second.to_token_stream()),
+            )
+        })
+        .collect();
+
+    ParsedCallInput {
+        path,
+        name: call.name,
+        args,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    #[test]
+    fn test_named_arguments() {
+        let parsed = parse_call_input(quote! { add, y = 3, x = 2 }).expect("should parse");
+        assert!(parsed.path.is_empty());
+        assert_eq!(parsed.name.to_string(), "add");
+        let args: Vec<_> = parsed
+            .args
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.as_ref().map(|v| v.to_string())))
+            .collect();
+        assert_eq!(
+            args,
+            vec![
+                ("y".to_string(), Some("3".to_string())),
+                ("x".to_string(), Some("2".to_string()))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_paths_and_values() {
+        let parsed = parse_call_input(quote! {
+            ::crate_a::math::scale, values, factor = HashMap::<u8, u8>::new().len(),
+        })
+        .expect("should parse");
+        assert_eq!(parsed.path.to_string(), ":: crate_a :: math ::");
+        assert_eq!(parsed.name.to_string(), "scale");
+        assert_eq!(parsed.args.len(), 2);
+        assert_eq!(parsed.args[0].0.to_string(), "values");
+        assert!(parsed.args[0].1.is_none());
+        assert_eq!(
+            parsed.args[1].1.as_ref().unwrap().to_string(),
+            "HashMap :: < u8 , u8 > :: new () . len ()"
+        );
+
+        let parsed = parse_call_input(quote! { noop }).expect("should parse");
+        assert!(parsed.args.is_empty());
+    }
+
+    #[test]
+    fn test_checked_call() {
+        let parsed =
+            parse_checked_call(quote! { [x y] [width] math::add, y = 3 }).expect("should parse");
+        let names = |idents: &[Ident]| idents.iter().map(Ident::to_string).collect::<Vec<_>>();
+        assert_eq!(names(&parsed.required), vec!["x", "y"]);
+        assert_eq!(names(&parsed.defaulted), vec!["width"]);
+        assert_eq!(parsed.call.path.to_string(), "math ::");
+        assert_eq!(parsed.call.args.len(), 1);
+
+        assert!(parse_checked_call(quote! { [] math::add }).is_err());
+    }
+
+    #[test]
+    fn test_invalid_input_is_error() {
+        assert!(parse_call_input(quote! { add(1, 2) }).is_err());
+        assert!(parse_call_input(quote! { add, x = }).is_err());
+        assert!(parse_call_input(quote! { add, 1 }).is_err());
+    }
+}
@@ -0,0 +1,142 @@
+use proc_macro2::{Delimiter, Group, Span, TokenStream};
+use quote::{quote, quote_spanned};
+use unsynn::{ErrorKind, TokenTree};
+
+/// Result type for the macro's parse and codegen paths
+pub type Result<T> = core::result::Result<T, ParseError>;
+
+/// A parse failure, reported as `compile_error!` at the offending token
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    /// Span of the token the error points at
+    pub span: Span,
+    /// Span of the last token the error points at, when it covers several
+    pub end: Span,
+    /// Message shown to the user
+    pub message: String,
+}
+
+impl ParseError {
+    /// Error with a message at `span`
+    pub fn new(span: Span, message: impl Into<String>) -> Self {
+        ParseError {
+            span,
+            end: span,
+            message: message.into(),
+        }
+    }
+
+    /// Error with a message covering the tokens from `start` to `end`
+    pub fn spanning(start: Span, end: Span, message: impl Into<String>) -> Self {
+        ParseError {
+            end,
+            ..Self::new(start, message)
+        }
+    }
+
+    /// Convert an unsynn error from parsing `input` as `what` (e.g. "function signature"),
+    /// pointing at the token where parsing stopped
+    pub fn from_unsynn(err: &unsynn::Error, input: &TokenStream, what: &str) -> Self {
+        let end_of_input = || {
+            Self::new(
+                Span::call_site(),
+                format!("failed to parse {what}: unexpected end of input"),
+            )
+        };
+        match &err.kind {
+            // unsynn consumes the offending token, so `at` holds the tokens after it
+            ErrorKind::UnexpectedToken { at, .. } if err.pos() != usize::MAX => {
+                let tokens: Vec<_> = input.clone().into_iter().collect();
+                tokens
+                    .len()
+                    .checked_sub(at.clone().count() + 1)
+                    .and_then(|index| tokens.get(index))
+                    .map_or_else(end_of_input, |token| Self::unexpected(token, what))
+            }
+            ErrorKind::UnexpectedToken { .. } => end_of_input(),
+            _ => Self::new(Span::call_site(), format!("failed to parse {what}: {err}")),
+        }
+    }
+
+    /// Error for a token that cannot appear at this point of `what`
+    pub fn unexpected(token: &TokenTree, what: &str) -> Self {
+        Self::new(
+            token.span(),
+            format!("failed to parse {what}: unexpected `{}`", describe(token)),
+        )
+    }
+
+    /// The `compile_error!` invocation reporting this error; braces make it valid in both
+    /// item and expression position. The compiler points from the span of `compile_error` to
+    /// that of the braces.
+    pub fn to_compile_error(&self) -> TokenStream {
+        let message = &self.message;
+        let mut braces = Group::new(Delimiter::Brace, quote! { #message });
+        braces.set_span(self.end);
+        quote_spanned! { self.span => compile_error! #braces }
+    }
+}
+
+/// Short description of a token; groups are shown by their delimiters only
+fn describe(token: &TokenTree) -> String {
+    match token {
+        TokenTree::Group(group) => match group.delimiter() {
+            Delimiter::Parenthesis => "( ... )".to_string(),
+            Delimiter::Brace => "{ ... }".to_string(),
+            Delimiter::Bracket => "[ ... ]".to_string(),
+            Delimiter::None => group.stream().to_string(),
+        },
+        other => other.to_string(),
+    }
+}
+
+/// Error at the first token left over in `tokens` after parsing `what`, if any
+pub fn expect_end(tokens: &mut unsynn::TokenIter, what: &str) -> Result<()> {
+    match tokens.next() {
+        Some(token) => Err(ParseError::unexpected(&token, what)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+    use unsynn::{IParse, Ident, ToTokens};
+
+    #[test]
+    fn test_unexpected_token() {
+        let input = quote! { 42 };
+        let mut it = input.to_token_iter();
+        let err = it.parse::<Ident>().unwrap_err();
+        let err = ParseError::from_unsynn(&err, &input, "name");
+        assert_eq!(err.message, "failed to parse name: unexpected `42`");
+    }
+
+    #[test]
+    fn test_unexpected_end() {
+        let input = quote! {};
+        let mut it = input.to_token_iter();
+        let err = it.parse::<Ident>().unwrap_err();
+        let err = ParseError::from_unsynn(&err, &input, "name");
+        assert_eq!(err.message, "failed to parse name: unexpected end of input");
+    }
+
+    #[test]
+    fn test_group_described_by_delimiters() {
+        let input = quote! { foo { a b c } };
+        let mut it = input.to_token_iter();
+        it.parse::<Ident>().unwrap();
+        let err = expect_end(&mut it, "name").unwrap_err();
+        assert_eq!(err.message, "failed to parse name: unexpected `{ ... }`");
+    }
+
+    #[test]
+    fn test_compile_error() {
+        let err = ParseError::new(Span::call_site(), "bad input");
+        assert_eq!(
+            err.to_compile_error().to_string(),
+            "compile_error ! { \"bad input\" }"
+        );
+    }
+}
@@ -1,40 +1,112 @@
 use proc_macro2::TokenStream;
 use unsynn::*;
 
-// Re-use the generics parser from our other module
-use crate::generics::GenericParams;
+// Re-use the angle-bracket-aware token parser from our other module
+use crate::error::{ParseError, Result, expect_end};
+use crate::generics::VerbatimUntil;
 
 unsynn! {
-    /// Input to fn_shape! macro: function_name or function_name<generics>
+    /// A single generic argument: a type, lifetime or const expression
+    pub struct GenericArg {
+        /// Argument tokens (everything until comma or closing angle bracket)
+        pub arg: VerbatimUntil<Either<Comma, Gt>>,
+    }
+
+    /// Generic arguments with angle brackets, e.g. `<i32, 4>`
+    pub struct GenericArgs {
+        /// Opening angle bracket
+        pub _lt: Lt,
+        /// Comma-delimited list of generic arguments
+        pub args: CommaDelimitedVec<GenericArg>,
+        /// Closing angle bracket
+        pub _gt: Gt,
+    }
+
+    /// A type path segment before the method name, e.g. `Wrapper::<u8>`
+    pub struct TypeSegment {
+        /// Segment name
+        pub name: Ident,
+        /// Optional turbofish generic arguments
+        pub generics: Option<Cons<PathSep, GenericArgs>>,
+    }
+
+    /// Input to fn_shape! macro: function_name, function_name<generics> or
+    /// Type::method<generics>
     pub struct FnShapeInput {
+        /// Optional type path of a method, each segment followed by `::`
+        pub type_path: Any<Cons<TypeSegment, PathSep>>,
         /// Function name
         pub name: Ident,
-        /// Optional generic parameters
-        pub generics: Option<GenericParams>,
+        /// Optional generic arguments
+        pub generics: Option<GenericArgs>,
     }
 }
 
+/// The type a method path names, e.g. `math::Wrapper::<u8>`
+pub struct MethodType {
+    /// Path to the type's module including the trailing `::`, empty for a type in scope
+    pub module_path: TokenStream,
+    /// The type as written, without the trailing `::`
+    pub self_type: TokenStream,
+    /// The type's name, e.g. `Wrapper`
+    pub name: Ident,
+}
+
 /// Parsed fn_shape input with extracted components
 pub struct ParsedFnShapeInput {
+    /// Type path including the trailing `::`, empty for free functions
+    pub type_path: TokenStream,
+    /// Set when the path names a method rather than a function in a module; the last segment
+    /// is taken as a type when it is capitalized or has generic arguments
+    pub method_type: Option<MethodType>,
     pub name: Ident,
     pub generics: Option<TokenStream>,
+    /// Each generic argument, e.g. `u16` for `convert<u16>`
+    pub generic_args: Vec<TokenStream>,
 }
 
 /// Parse fn_shape! macro input from TokenStream
-pub fn parse_fn_shape_input(input: TokenStream) -> ParsedFnShapeInput {
+pub fn parse_fn_shape_input(input: TokenStream) -> Result<ParsedFnShapeInput> {
     let mut it = input.to_token_iter();
 
-    match it.parse::<FnShapeInput>() {
-        Ok(shape_input) => {
-            let name = shape_input.name;
-            let generics = shape_input.generics.map(|g| g.to_token_stream());
+    let shape_input = it
+        .parse::<FnShapeInput>()
+        .map_err(|err| ParseError::from_unsynn(&err, &input, "fn_shape! input"))?;
+    expect_end(&mut it, "fn_shape! input")?;
 
-            ParsedFnShapeInput { name, generics }
-        }
-        Err(err) => {
-            panic!("Failed to parse fn_shape input: {}", err);
-        }
-    }
+    let segments = &shape_input.type_path.0;
+    let method_type = segments.split_last().and_then(|(last, modules)| {
+        let segment = &last.value.first;
+        let is_type =
+            segment.generics.is_some() || segment.name.to_string().starts_with(char::is_uppercase);
+        is_type.then(|| {
+            let module_path: TokenStream = modules.iter().map(|s| s.to_token_stream()).collect();
+            let segment_tokens = segment.to_token_stream();
+            MethodType {
+                self_type: quote::quote! { #module_path #segment_tokens },
+                module_path,
+                name: segment.name.clone(),
+            }
+        })
+    });
+    let generic_args = shape_input
+        .generics
+        .as_ref()
+        .map(|g| {
+            g.args
+                .0
+                .iter()
+                .map(|d| d.value.arg.to_token_stream())
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(ParsedFnShapeInput {
+        type_path: shape_input.type_path.to_token_stream(),
+        method_type,
+        name: shape_input.name,
+        generics: shape_input.generics.map(|g| g.to_token_stream()),
+        generic_args,
+    })
 }
 
 #[cfg(test)]
@@ -45,15 +117,16 @@ mod tests {
     #[test]
     fn test_simple_function_name() {
         let input = quote! { add };
-        let parsed = parse_fn_shape_input(input);
+        let parsed = parse_fn_shape_input(input).expect("should parse");
         assert_eq!(parsed.name.to_string(), "add");
+        assert!(parsed.type_path.is_empty());
         assert!(parsed.generics.is_none());
     }
 
     #[test]
     fn test_function_with_single_generic() {
         let input = quote! { generic_add<T> };
-        let parsed = parse_fn_shape_input(input);
+        let parsed = parse_fn_shape_input(input).expect("should parse");
         assert_eq!(parsed.name.to_string(), "generic_add");
         assert!(parsed.generics.is_some());
         assert_eq!(parsed.generics.unwrap().to_string().trim(), "< T >");
@@ -62,7 +135,7 @@ mod tests {
     #[test]
     fn test_function_with_multiple_generics() {
         let input = quote! { multi_ty_param_fn<T, U> };
-        let parsed = parse_fn_shape_input(input);
+        let parsed = parse_fn_shape_input(input).expect("should parse");
         assert_eq!(parsed.name.to_string(), "multi_ty_param_fn");
         assert!(parsed.generics.is_some());
         assert_eq!(parsed.generics.unwrap().to_string().trim(), "< T , U >");
@@ -71,7 +144,7 @@ mod tests {
     #[test]
     fn test_function_with_bounded_generics() {
         let input = quote! { bounded_fn<U: Clone> };
-        let parsed = parse_fn_shape_input(input);
+        let parsed = parse_fn_shape_input(input).expect("should parse");
         assert_eq!(parsed.name.to_string(), "bounded_fn");
         assert!(parsed.generics.is_some());
         assert_eq!(parsed.generics.unwrap().to_string().trim(), "< U : Clone >");
@@ -80,7 +153,7 @@ mod tests {
     #[test]
     fn test_function_with_complex_generics() {
         let input = quote! { nested_fn<T: Add<Output = T>> };
-        let parsed = parse_fn_shape_input(input);
+        let parsed = parse_fn_shape_input(input).expect("should parse");
         assert_eq!(parsed.name.to_string(), "nested_fn");
         assert!(parsed.generics.is_some());
         assert_eq!(
@@ -88,4 +161,81 @@ mod tests {
             "< T : Add < Output = T > >"
         );
     }
+
+    #[test]
+    fn test_function_with_const_argument() {
+        let input = quote! { chunk<4> };
+        let parsed = parse_fn_shape_input(input).expect("should parse");
+        assert_eq!(parsed.name.to_string(), "chunk");
+        assert_eq!(parsed.generics.unwrap().to_string().trim(), "< 4 >");
+    }
+
+    #[test]
+    fn test_function_with_nested_type_arguments() {
+        let input = quote! { mixed<Vec<u8>, { N + 1 }> };
+        let parsed = parse_fn_shape_input(input).expect("should parse");
+        assert_eq!(parsed.name.to_string(), "mixed");
+        assert_eq!(
+            parsed.generics.unwrap().to_string().trim(),
+            "< Vec < u8 > , { N + 1 } >"
+        );
+    }
+
+    #[test]
+    fn test_method_path() {
+        let input = quote! { Counter::add };
+        let parsed = parse_fn_shape_input(input).expect("should parse");
+        assert_eq!(parsed.type_path.to_string(), "Counter ::");
+        assert!(parsed.method_type.is_some());
+        assert_eq!(parsed.name.to_string(), "add");
+        assert!(parsed.generics.is_none());
+    }
+
+    #[test]
+    fn test_module_path() {
+        let parsed = parse_fn_shape_input(quote! { crate::math::add }).expect("should parse");
+        assert_eq!(parsed.type_path.to_string(), "crate :: math ::");
+        assert!(parsed.method_type.is_none());
+        let parsed = parse_fn_shape_input(quote! { add }).expect("should parse");
+        assert!(parsed.method_type.is_none());
+    }
+
+    #[test]
+    fn test_generic_method_path() {
+        let input = quote! { crate::Wrapper::<u8>::convert<u16> };
+        let parsed = parse_fn_shape_input(input).expect("should parse");
+        assert_eq!(
+            parsed.type_path.to_string(),
+            "crate :: Wrapper :: < u8 > ::"
+        );
+        let method_type = parsed.method_type.expect("should be a method");
+        assert_eq!(method_type.module_path.to_string(), "crate ::");
+        assert_eq!(
+            method_type.self_type.to_string(),
+            "crate :: Wrapper :: < u8 >"
+        );
+        assert_eq!(method_type.name.to_string(), "Wrapper");
+        assert_eq!(parsed.name.to_string(), "convert");
+        assert_eq!(parsed.generics.unwrap().to_string().trim(), "< u16 >");
+        assert_eq!(parsed.generic_args[0].to_string(), "u16");
+    }
+
+    #[test]
+    fn test_invalid_input_is_error() {
+        let err = parse_fn_shape_input(quote! { 42 })
+            .err()
+            .expect("should fail");
+        assert_eq!(
+            err.message,
+            "failed to parse fn_shape! input: unexpected `42`"
+        );
+
+        let err = parse_fn_shape_input(quote! { add(1, 2) })
+            .err()
+            .expect("should fail");
+        assert_eq!(
+            err.message,
+            "failed to parse fn_shape! input: unexpected `( ... )`"
+        );
+    }
 }
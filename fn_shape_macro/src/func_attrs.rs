@@ -0,0 +1,218 @@
+use proc_macro2::TokenStream;
+use unsynn::*;
+
+keyword! {
+    /// The "doc" attribute name.
+    pub KDoc = "doc";
+}
+
+unsynn! {
+    /// An outer attribute: `#[...]`
+    pub struct Attribute {
+        /// The "#" sigil
+        pub _pound: Pound,
+        /// Attribute contents in brackets
+        pub body: BracketGroup,
+    }
+
+    /// The contents of a doc attribute: `doc = "..."`
+    pub struct DocAttribute {
+        /// The "doc" name
+        pub _doc: KDoc,
+        /// Equals sign
+        pub _eq: Assign,
+        /// Documentation text as a string literal
+        pub text: Literal,
+    }
+}
+
+/// Lint level attributes that also need to reach the function body
+const LINT_ATTRIBUTES: &[&str] = &["allow", "warn", "deny", "forbid", "expect"];
+
+impl Attribute {
+    /// The first segment of the attribute path, e.g. `doc` or `cfg`
+    pub fn name(&self) -> Option<String> {
+        let body = self.body.0.stream();
+        let mut it = body.to_token_iter();
+        it.parse::<Ident>().ok().map(|ident| ident.to_string())
+    }
+
+    /// The last segment of the attribute path, e.g. `facet_fn` for `fn_shape_macro::facet_fn`
+    pub fn path_last_segment(&self) -> Option<String> {
+        let mut last = None;
+        for token in self.body.0.stream() {
+            match token {
+                TokenTree::Ident(ident) => last = Some(ident.to_string()),
+                TokenTree::Punct(punct) if punct.as_char() == ':' => {}
+                _ => break,
+            }
+        }
+        last
+    }
+
+    /// The arguments in parentheses after the attribute path, e.g. `register = false` for
+    /// `#[facet_fn(register = false)]`; empty when there are none
+    pub fn arguments(&self) -> TokenStream {
+        for token in self.body.0.stream() {
+            match token {
+                TokenTree::Ident(_) => {}
+                TokenTree::Punct(punct) if punct.as_char() == ':' => {}
+                TokenTree::Group(group) if group.delimiter() == Delimiter::Parenthesis => {
+                    return group.stream();
+                }
+                _ => break,
+            }
+        }
+        TokenStream::new()
+    }
+
+    /// The unescaped text of a `#[doc = "..."]` attribute
+    pub fn doc_text(&self) -> Option<String> {
+        let body = self.body.0.stream();
+        let mut it = body.to_token_iter();
+        it.parse::<DocAttribute>()
+            .ok()
+            .and_then(|doc| string_value(&doc.text.to_string()))
+    }
+
+    /// Whether this is a `#[cfg(...)]` attribute
+    pub fn is_cfg(&self) -> bool {
+        self.name().as_deref() == Some("cfg")
+    }
+
+    /// Whether this sets a lint level, e.g. `#[allow(...)]`
+    pub fn is_lint(&self) -> bool {
+        self.name()
+            .is_some_and(|name| LINT_ATTRIBUTES.contains(&name.as_str()))
+    }
+
+    /// Convert the attribute to TokenStream for use with quote!
+    pub fn tokens(&self) -> TokenStream {
+        self.to_token_stream()
+    }
+}
+
+/// Concatenate the doc comments in `attrs`, one line per attribute, dropping the single
+/// leading space that `///` comments carry
+pub fn collect_doc(attrs: &[Attribute]) -> String {
+    attrs
+        .iter()
+        .filter_map(Attribute::doc_text)
+        .map(|line| match line.strip_prefix(' ') {
+            Some(stripped) => stripped.to_string(),
+            None => line,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The value of a plain or raw string literal, as written in source
+fn string_value(literal: &str) -> Option<String> {
+    if let Some(raw) = literal.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        let inner = &raw[hashes..raw.len() - hashes];
+        return inner
+            .strip_prefix('"')
+            .and_then(|s| s.strip_suffix('"'))
+            .map(str::to_string);
+    }
+    literal
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .map(unescape)
+}
+
+/// Resolve the escapes that can appear in a doc string literal
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some('0') => out.push('\0'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    fn parse_attributes(input: TokenStream) -> Vec<Attribute> {
+        let mut it = input.to_token_iter();
+        it.parse::<Vec<Attribute>>().expect("vec always parses")
+    }
+
+    #[test]
+    fn test_no_attributes() {
+        let attrs = parse_attributes(quote! { fn f() {} });
+        assert!(attrs.is_empty());
+        assert_eq!(collect_doc(&attrs), "");
+    }
+
+    #[test]
+    fn test_attribute_names() {
+        let attrs = parse_attributes(quote! {
+            #[inline]
+            #[cfg(test)]
+            #[allow(dead_code)]
+            #[must_use = "use it"]
+        });
+        assert_eq!(attrs.len(), 4);
+        assert_eq!(attrs[0].name().as_deref(), Some("inline"));
+        assert_eq!(attrs[0].path_last_segment().as_deref(), Some("inline"));
+        assert!(attrs[1].is_cfg());
+        assert!(attrs[2].is_lint());
+        assert!(!attrs[3].is_lint());
+        assert!(attrs[3].doc_text().is_none());
+    }
+
+    #[test]
+    fn test_path_last_segment() {
+        let attrs = parse_attributes(quote! { #[fn_shape_macro::facet_fn] #[cfg(test)] });
+        assert_eq!(attrs[0].name().as_deref(), Some("fn_shape_macro"));
+        assert_eq!(attrs[0].path_last_segment().as_deref(), Some("facet_fn"));
+        assert_eq!(attrs[1].path_last_segment().as_deref(), Some("cfg"));
+    }
+
+    #[test]
+    fn test_arguments() {
+        let attrs = parse_attributes(quote! { #[facet_fn(register = false)] #[facet_fn] });
+        assert_eq!(attrs[0].arguments().to_string(), "register = false");
+        assert!(attrs[1].arguments().is_empty());
+    }
+
+    #[test]
+    fn test_doc_comments() {
+        let attrs = parse_attributes(quote! {
+            /// Adds two numbers.
+            ///
+            /// Returns the "sum".
+            #[inline]
+        });
+        assert_eq!(attrs.len(), 4);
+        assert_eq!(
+            collect_doc(&attrs),
+            "Adds two numbers.\n\nReturns the \"sum\"."
+        );
+    }
+
+    #[test]
+    fn test_explicit_doc_attribute() {
+        let attrs = parse_attributes(quote! { #[doc = "first\tline"] });
+        assert_eq!(collect_doc(&attrs), "first\tline");
+
+        let attrs = parse_attributes(quote! { #[doc = r#"raw "text""#] });
+        assert_eq!(collect_doc(&attrs), "raw \"text\"");
+    }
+}
@@ -2,38 +2,28 @@
 use proc_macro2::TokenStream;
 use unsynn::*;
 
-/// Parses tokens until `C` is found on the current token tree level.
-pub type VerbatimUntil<C> = Many<Cons<Except<C>, TokenTree>>;
+#[cfg(test)]
+use crate::error::{ParseError, Result};
 
 unsynn! {
     /// A function signature followed by its body
     pub struct FunctionWithBody {
         /// Everything before the function body (return type, etc.) - optional
-        pub prefix: Option<VerbatimUntil<BraceGroup>>,
+        pub prefix: Option<Many<Cons<Except<BraceGroup>, TokenTree>>>,
         /// The function body in braces
         pub body: BraceGroup,
     }
 }
 
-/// Parse function body from tokens using declarative parsing
-/// Returns the function body as TokenStream
+/// Parse the function body from tokens such as `-> i32 { x + y }`, erroring when there is
+/// none
 #[cfg(test)]
-pub fn parse_function_body(tokens: &[TokenTree]) -> TokenStream {
-    // Convert tokens to TokenStream for parsing
-    let mut token_stream = TokenStream::new();
-    for token in tokens {
-        token_stream.extend(core::iter::once(token.clone()));
-    }
-
+pub fn parse_function_body(tokens: &[TokenTree]) -> Result<TokenStream> {
+    let token_stream: TokenStream = tokens.iter().cloned().collect();
     let mut it = token_stream.to_token_iter();
-
-    match it.parse::<FunctionWithBody>() {
-        Ok(func_with_body) => func_with_body.body.to_token_stream(),
-        Err(_) => {
-            // No function body found, return empty braces
-            quote::quote! { {} }
-        }
-    }
+    it.parse::<FunctionWithBody>()
+        .map(|func_with_body| func_with_body.body.to_token_stream())
+        .map_err(|err| ParseError::from_unsynn(&err, &token_stream, "function body"))
 }
 
 #[cfg(test)]
@@ -44,7 +34,7 @@ mod tests {
     #[test]
     fn test_simple_function_body() {
         let input: Vec<TokenTree> = quote! { { x + y } }.into_iter().collect();
-        let body = parse_function_body(&input);
+        let body = parse_function_body(&input).expect("should parse");
         assert_eq!(body.to_string().trim(), "{ x + y }");
     }
 
@@ -57,7 +47,7 @@ mod tests {
         } }
         .into_iter()
         .collect();
-        let body = parse_function_body(&input);
+        let body = parse_function_body(&input).expect("should parse");
         let body_str = body.to_string();
         assert!(body_str.contains("let result"));
         assert!(body_str.contains("println !"));
@@ -69,15 +59,15 @@ mod tests {
     fn test_function_with_trailing_tokens() {
         // Simulate tokens like: -> i32 { x + y }
         let input: Vec<TokenTree> = quote! { -> i32 { x + y } }.into_iter().collect();
-        let body = parse_function_body(&input);
+        let body = parse_function_body(&input).expect("should parse");
         assert_eq!(body.to_string().trim(), "{ x + y }");
     }
 
     #[test]
     fn test_no_function_body() {
         let input: Vec<TokenTree> = quote! { -> i32 }.into_iter().collect();
-        let body = parse_function_body(&input);
-        assert_eq!(body.to_string().trim(), "{ }");
+        let err = parse_function_body(&input).expect_err("should fail");
+        assert!(err.message.starts_with("failed to parse function body"));
     }
 
     #[test]
@@ -89,7 +79,7 @@ mod tests {
         } }
         .into_iter()
         .collect();
-        let body = parse_function_body(&input);
+        let body = parse_function_body(&input).expect("should parse");
         let body_str = body.to_string();
         assert!(body_str.contains("if condition"));
         assert!(body_str.contains("inner_block"));
@@ -1,14 +1,51 @@
-use proc_macro2::TokenStream;
+use proc_macro2::{Delimiter, Group, Punct, Spacing, Span, TokenStream};
 use unsynn::*;
 
+use crate::attr_args::{ParamArgs, ParamDefault};
+use crate::error::{ParseError, Result, expect_end};
+use crate::func_attrs::Attribute;
+use crate::impl_block::KImpl;
+// Re-use the lifetime parser for `&'a self`
+use crate::lifetimes::Lifetime;
+
 /// Parses tokens until `C` is found on the current token tree level.
-pub type VerbatimUntil<C> = Many<Cons<Except<C>, TokenTree>>;
+pub type VerbatimUntil<C> = Many<Cons<Except<C>, TypeTokenTree>>;
+
+/// A `<` that is re-emitted with its original spacing.
+pub type RawLt = Cons<Expect<Lt>, Punct>;
+/// A `>` that is re-emitted with its original spacing, so `>>` stays joint.
+pub type RawGt = Cons<Expect<Gt>, Punct>;
+/// A `->` that is re-emitted with its original spacing.
+pub type RawRArrow = Cons<Expect<RArrow>, Punct, Punct>;
 
 unsynn! {
-    /// A function parameter with name and type
-    pub struct Parameter {
-        /// Parameter name
+    /// Like `AngleTokenTree` in `generics`, but keeps the original spacing of `<`, `>` and
+    /// `->` so that parameter types round-trip exactly.
+    #[derive(Clone)]
+    pub struct TypeTokenTree(
+        #[allow(clippy::type_complexity)]
+        pub Either<Cons<RawLt, Vec<Cons<Except<Gt>, TypeTokenTree>>, RawGt>, RawRArrow, TokenTree>,
+    );
+
+    /// Pattern tokens before a parameter's type annotation; `::` is kept in the pattern
+    pub struct ParamPattern(
+        pub Many<Either<PathSep, Cons<Except<Colon>, TypeTokenTree>>>,
+    );
+
+    /// A plain binding pattern: `x` or `mut x`
+    pub struct BindingPattern {
+        /// Optional "mut" keyword
+        pub mutability: Option<KMut>,
+        /// Bound name
         pub name: Ident,
+    }
+
+    /// A function parameter as written: attributes, pattern, colon and type
+    pub struct RawParameter {
+        /// Outer attributes, e.g. `#[facet(default)]`
+        pub attributes: Vec<Attribute>,
+        /// Parameter pattern
+        pub pattern: ParamPattern,
         /// Colon separator
         pub _colon: Colon,
         /// Parameter type (everything until comma or end)
@@ -16,23 +53,398 @@ unsynn! {
     }
 }
 
+keyword! {
+    /// The "mut" keyword.
+    pub KMut = "mut";
+    /// The "self" keyword.
+    pub KSelfValue = "self";
+}
+
+unsynn! {
+    /// A method receiver, tried in order so `self: Type` wins over a plain `self`
+    pub enum Receiver {
+        /// `self: Box<Self>` or `mut self: Box<Self>`
+        Typed {
+            mutability: Option<KMut>,
+            _self: KSelfValue,
+            _colon: Colon,
+            self_type: VerbatimUntil<Comma>,
+        },
+        /// `&self`, `&mut self` or `&'a self`
+        Ref {
+            _and: And,
+            lifetime: Option<Lifetime>,
+            mutability: Option<KMut>,
+            _self: KSelfValue,
+        },
+        /// `self` or `mut self`
+        Value {
+            mutability: Option<KMut>,
+            _self: KSelfValue,
+        },
+    }
+}
+
+impl Receiver {
+    /// Span of the receiver's first token, for diagnostics
+    pub fn span(&self) -> Span {
+        self.to_token_stream()
+            .into_iter()
+            .next()
+            .map_or_else(Span::call_site, |token| token.span())
+    }
+
+    /// Whether a reference receiver is `&mut self`
+    pub fn is_ref_mut(&self) -> bool {
+        matches!(
+            self,
+            Receiver::Ref {
+                mutability: Some(_),
+                ..
+            }
+        )
+    }
+}
+
+unsynn! {
+    /// The leading tokens of a parameter type that decide how the argument is passed
+    pub enum TypeKind {
+        /// `&mut T` or `&'a mut T`
+        RefMut(Cons<And, Option<Lifetime>, KMut>),
+        /// `&T` or `&'a T`
+        Ref(And),
+        /// `impl Trait`
+        ImplTrait(KImpl),
+    }
+}
+
+/// How an argument is passed; anything that is not a reference or `impl Trait` is owned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamKind {
+    Owned,
+    Ref,
+    RefMut,
+    ImplTrait,
+}
+
+/// Whether `ident` occurs anywhere in `tokens`, including inside groups
+pub fn contains_ident(tokens: TokenStream, ident: &str) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(found) => found == ident,
+        TokenTree::Group(group) => contains_ident(group.stream(), ident),
+        _ => false,
+    })
+}
+
+/// `tokens` with each `Self` replaced by `self_type`, qualified as `<Type>` before a `::` so
+/// that `Self::Item` stays a path
+pub fn replace_self(tokens: TokenStream, self_type: &TokenStream) -> TokenStream {
+    let mut out = Vec::new();
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Ident(ident) if ident == "Self" => {
+                let before_path = matches!(
+                    tokens.peek(),
+                    Some(TokenTree::Punct(punct)) if punct.as_char() == ':'
+                );
+                let replaced = if before_path {
+                    quote::quote! { < #self_type > }
+                } else {
+                    self_type.clone()
+                };
+                out.push(TokenTree::Group(Group::new(Delimiter::None, replaced)));
+            }
+            TokenTree::Group(group) => {
+                let mut replaced =
+                    Group::new(group.delimiter(), replace_self(group.stream(), self_type));
+                replaced.set_span(group.span());
+                out.push(TokenTree::Group(replaced));
+            }
+            other => out.push(other),
+        }
+    }
+    out.into_iter().collect()
+}
+
+/// `ty` with each `impl Trait` replaced by a new type parameter `__Impl{n}`, numbered on from
+/// `next`, along with each new parameter and its bounds. The bounds run until a comma or an
+/// unmatched `>` at the level of the `impl`.
+pub fn name_impl_traits(
+    ty: TokenStream,
+    next: &mut usize,
+) -> (TokenStream, Vec<(Ident, TokenStream)>) {
+    let mut out = Vec::new();
+    let mut named = Vec::new();
+    let mut tokens = ty.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Ident(ident) if ident == "impl" => {
+                let mut bounds = Vec::new();
+                let mut depth = 0usize;
+                let mut after_minus = false;
+                while let Some(next) = tokens.peek() {
+                    if let TokenTree::Punct(punct) = next {
+                        match punct.as_char() {
+                            ',' if depth == 0 => break,
+                            '>' if !after_minus && depth == 0 => break,
+                            '>' if !after_minus => depth -= 1,
+                            '<' => depth += 1,
+                            _ => {}
+                        }
+                        after_minus = punct.as_char() == '-';
+                    } else {
+                        after_minus = false;
+                    }
+                    bounds.extend(tokens.next());
+                }
+                let param = Ident::new(&format!("__Impl{next}"), ident.span());
+                *next += 1;
+                out.push(TokenTree::Ident(param.clone()));
+                named.push((param, bounds.into_iter().collect()));
+            }
+            TokenTree::Group(group) => {
+                let (stream, inner) = name_impl_traits(group.stream(), next);
+                let mut replaced = Group::new(group.delimiter(), stream);
+                replaced.set_span(group.span());
+                out.push(TokenTree::Group(replaced));
+                named.extend(inner);
+            }
+            other => out.push(other),
+        }
+    }
+    (out.into_iter().collect(), named)
+}
+
+/// `tokens` as text with a space between every two tokens except after a joint punct, e.g.
+/// `( a , b )`; unlike `to_string` this does not depend on the proc_macro implementation
+pub fn spaced_text(tokens: TokenStream) -> String {
+    let mut text = String::new();
+    let mut joint = true;
+    for token in tokens {
+        if !joint {
+            text.push(' ');
+        }
+        joint = false;
+        match token {
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::None => ("", ""),
+                };
+                let inner = spaced_text(group.stream());
+                if inner.is_empty() {
+                    text.push_str(&format!("{open}{close}"));
+                } else {
+                    text.push_str(&format!("{open} {inner} {close}"));
+                }
+            }
+            TokenTree::Punct(punct) => {
+                text.push(punct.as_char());
+                joint = punct.spacing() == Spacing::Joint;
+            }
+            other => text.push_str(&other.to_string()),
+        }
+    }
+    text
+}
+
+/// `tokens` with every `<` and `>` spaced alone, so rendered types read `Vec < Vec < u8 > >`
+/// like other generated strings; `->` keeps its joint `-`
+pub fn normalize_angle_spacing(tokens: TokenStream) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|token| match token {
+            TokenTree::Punct(punct) if matches!(punct.as_char(), '<' | '>') => {
+                let mut alone = Punct::new(punct.as_char(), Spacing::Alone);
+                alone.set_span(punct.span());
+                TokenTree::Punct(alone)
+            }
+            TokenTree::Group(group) => {
+                let mut normalized =
+                    Group::new(group.delimiter(), normalize_angle_spacing(group.stream()));
+                normalized.set_span(group.span());
+                TokenTree::Group(normalized)
+            }
+            other => other,
+        })
+        .collect()
+}
+
+/// A function parameter with name and type
+pub struct Parameter {
+    /// Identifier the argument is bound to when forwarding: the binding itself for `x` or
+    /// `mut x`, otherwise a synthesized `__arg{index}`
+    pub name: Ident,
+    /// Name recorded in the shape: the binding, or `_{index}` for a wildcard or destructuring
+    /// pattern
+    pub display_name: String,
+    /// The pattern as written, used on the real implementation
+    pub pattern: TokenStream,
+    /// Whether the pattern is anything other than a plain binding
+    pub is_pattern: bool,
+    /// Parameter type (everything until comma or end)
+    pub param_type: VerbatimUntil<Comma>,
+    /// Attributes other than `#[facet(...)]`, kept on the real implementation
+    pub attributes: Vec<Attribute>,
+    /// The default from `#[facet(default)]`, which lets callers omit the argument
+    pub default: Option<ParamDefault>,
+}
+
+/// Whether `attr` is a parameter's `#[facet(...)]`
+fn is_facet_attribute(attr: &Attribute) -> bool {
+    attr.name().as_deref() == Some("facet")
+}
+
 impl Parameter {
+    /// Resolve the binding and display name of a parsed parameter at `index`
+    fn from_raw(raw: RawParameter, index: usize) -> Result<Self> {
+        let mut args = ParamArgs::default();
+        let mut attributes = Vec::new();
+        for attr in raw.attributes {
+            if is_facet_attribute(&attr) {
+                args = args.parse(attr.arguments())?;
+            } else if attr.is_cfg() || attr.name().as_deref() == Some("cfg_attr") {
+                let span = attr.tokens().into_iter().next().map(|t| t.span());
+                return Err(ParseError::new(
+                    span.unwrap_or_else(Span::call_site),
+                    "#[facet_fn] does not support `cfg` on parameters",
+                ));
+            } else {
+                attributes.push(attr);
+            }
+        }
+
+        let pattern = raw.pattern.to_token_stream();
+        let mut it = pattern.to_token_iter();
+        let binding = it
+            .parse::<Cons<BindingPattern, EndOfStream>>()
+            .ok()
+            .map(|cons| cons.first.name)
+            .filter(|name| name != "_");
+
+        let (name, display_name, is_pattern) = match binding {
+            Some(name) => {
+                let display_name = name.to_string();
+                (name, display_name, false)
+            }
+            None => {
+                let name = Ident::new(&format!("__arg{}", index), Span::call_site());
+                (name, format!("_{}", index), true)
+            }
+        };
+
+        Ok(Parameter {
+            name,
+            display_name,
+            pattern,
+            is_pattern,
+            param_type: raw.param_type,
+            attributes,
+            default: args.default,
+        })
+    }
+
     /// Convert the parameter type to TokenStream for use with quote!
     pub fn param_type_tokens(&self) -> TokenStream {
         self.param_type.to_token_stream()
     }
+
+    /// The pattern as written, spaced token by token, e.g. `( a , b )`
+    pub fn pattern_text(&self) -> String {
+        spaced_text(self.pattern.clone())
+    }
+
+    /// The parameter type as a string for the shape
+    pub fn type_string(&self) -> String {
+        normalize_angle_spacing(self.param_type_tokens()).to_string()
+    }
+
+    /// How the argument is passed, from the leading tokens of its type
+    pub fn kind(&self) -> ParamKind {
+        let tokens = self.param_type_tokens();
+        let mut it = tokens.to_token_iter();
+        match it.parse::<TypeKind>() {
+            Ok(TypeKind::RefMut(_)) => ParamKind::RefMut,
+            Ok(TypeKind::Ref(_)) => ParamKind::Ref,
+            Ok(TypeKind::ImplTrait(_)) => ParamKind::ImplTrait,
+            Err(_) => ParamKind::Owned,
+        }
+    }
+
+    /// Whether the type contains an `impl Trait` anywhere and so cannot be named
+    pub fn has_impl_trait(&self) -> bool {
+        contains_ident(self.param_type_tokens(), "impl")
+    }
+}
+
+/// A parameter list with every `#[facet(...)]` removed, for re-emitting a method as written
+pub fn strip_facet_attributes(params: TokenStream) -> TokenStream {
+    let mut out = Vec::new();
+    let mut tokens = params.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        if let TokenTree::Punct(pound) = &token
+            && pound.as_char() == '#'
+            && let Some(TokenTree::Group(group)) = tokens.peek()
+            && group.delimiter() == proc_macro2::Delimiter::Bracket
+            && matches!(group.stream().into_iter().next(), Some(TokenTree::Ident(ident)) if ident == "facet")
+        {
+            tokens.next();
+            continue;
+        }
+        out.push(token);
+    }
+    out.into_iter().collect()
 }
 
 /// Parse function parameters from a TokenStream (content of parentheses)
 /// Returns a Vec of Parameter structs
+#[cfg(test)]
 pub fn parse_fn_parameters(params_ts: TokenStream) -> Vec<Parameter> {
+    parse_receiver_and_parameters(params_ts)
+        .expect("should parse")
+        .1
+}
+
+/// Parse an optional method receiver followed by the function parameters
+pub fn parse_receiver_and_parameters(
+    params_ts: TokenStream,
+) -> Result<(Option<Receiver>, Vec<Parameter>)> {
     let mut it = params_ts.to_token_iter();
 
-    // Parse as comma-delimited list of parameters
-    match it.parse::<CommaDelimitedVec<Parameter>>() {
-        Ok(params) => params.0.into_iter().map(|delim| delim.value).collect(),
-        Err(_) => Vec::new(), // Empty parameter list
+    // A receiver is only ever the first parameter
+    let receiver = it
+        .parse::<Option<Cons<Receiver, Either<Comma, EndOfStream>>>>()
+        .ok()
+        .flatten()
+        .map(|cons| cons.first);
+
+    // Parse the rest as comma-delimited list of parameters
+    let params = it
+        .parse::<CommaDelimitedVec<RawParameter>>()
+        .map_err(|err| ParseError::from_unsynn(&err, &params_ts, "parameters"))?
+        .0
+        .into_iter()
+        .enumerate()
+        .map(|(index, delim)| Parameter::from_raw(delim.value, index))
+        .collect::<Result<_>>()?;
+
+    // Anything left over is a parameter we could not parse; name the common mistake
+    let rest: Vec<_> = it.clone().collect();
+    if let Some(token) = rest
+        .iter()
+        .find(|t| matches!(t, TokenTree::Ident(ident) if ident == "self"))
+    {
+        return Err(ParseError::new(
+            token.span(),
+            "`self` is only allowed as the first parameter",
+        ));
     }
+    expect_end(&mut it, "parameters")?;
+
+    Ok((receiver, params))
 }
 
 #[cfg(test)]
@@ -83,4 +495,329 @@ mod tests {
             "Vec < Option < u64 >>"
         );
     }
+
+    #[test]
+    fn test_generic_types_with_commas() {
+        let input = quote! { m: HashMap<String, i32>, r: Result<u8, E> };
+        let params = parse_fn_parameters(input);
+        assert_eq!(params.len(), 2);
+        assert_eq!(params[0].name.to_string(), "m");
+        assert_eq!(
+            params[0].param_type.tokens_to_string().trim(),
+            "HashMap < String , i32 >"
+        );
+        assert_eq!(params[1].name.to_string(), "r");
+        assert_eq!(
+            params[1].param_type.tokens_to_string().trim(),
+            "Result < u8 , E >"
+        );
+    }
+
+    #[test]
+    fn test_nested_generic_types() {
+        let input = quote! { data: Vec<HashMap<String, Vec<(u8, u16)>>>, n: usize };
+        let params = parse_fn_parameters(input);
+        assert_eq!(params.len(), 2);
+        assert_eq!(
+            params[0].param_type.tokens_to_string().trim(),
+            "Vec < HashMap < String , Vec < (u8 , u16) >> >"
+        );
+        assert_eq!(params[1].param_type.tokens_to_string().trim(), "usize");
+    }
+
+    #[test]
+    fn test_impl_fn_type() {
+        let input = quote! { f: impl Fn(A, B) -> C, x: A };
+        let params = parse_fn_parameters(input);
+        assert_eq!(params.len(), 2);
+        assert_eq!(
+            params[0].param_type.tokens_to_string().trim(),
+            "impl Fn (A , B) -> C"
+        );
+        assert_eq!(params[1].name.to_string(), "x");
+    }
+
+    #[test]
+    fn test_boxed_closure_returning_generic() {
+        let input = quote! { f: Box<dyn Fn(u8) -> Result<u8, E>>, y: u8 };
+        let params = parse_fn_parameters(input);
+        assert_eq!(params.len(), 2);
+        assert_eq!(
+            params[0].param_type.tokens_to_string().trim(),
+            "Box < dyn Fn (u8) -> Result < u8 , E >>"
+        );
+        assert_eq!(params[1].name.to_string(), "y");
+    }
+
+    #[test]
+    fn test_qualified_path_type() {
+        let input =
+            quote! { item: <T as Iterator>::Item, map: <HashMap<K, V> as IntoIterator>::IntoIter };
+        let params = parse_fn_parameters(input);
+        assert_eq!(params.len(), 2);
+        assert_eq!(
+            params[0].param_type.tokens_to_string().trim(),
+            "< T as Iterator > :: Item"
+        );
+        assert_eq!(
+            params[1].param_type.tokens_to_string().trim(),
+            "< HashMap < K , V > as IntoIterator > :: IntoIter"
+        );
+    }
+
+    #[test]
+    fn test_trailing_comma() {
+        let input = quote! { x: Option<(u8, u8)>, };
+        let params = parse_fn_parameters(input);
+        assert_eq!(params.len(), 1);
+        assert_eq!(
+            params[0].param_type.tokens_to_string().trim(),
+            "Option < (u8 , u8) >"
+        );
+    }
+
+    #[test]
+    fn test_mut_binding() {
+        let input = quote! { mut x: i32, y: i32 };
+        let params = parse_fn_parameters(input);
+        assert_eq!(params.len(), 2);
+        assert_eq!(params[0].name.to_string(), "x");
+        assert_eq!(params[0].display_name, "x");
+        assert_eq!(params[0].pattern.to_string(), "mut x");
+        assert!(!params[0].is_pattern);
+        assert!(!params[1].is_pattern);
+    }
+
+    #[test]
+    fn test_wildcard_pattern() {
+        let input = quote! { x: u8, _: u8 };
+        let params = parse_fn_parameters(input);
+        assert_eq!(params.len(), 2);
+        assert_eq!(params[1].name.to_string(), "__arg1");
+        assert_eq!(params[1].display_name, "_1");
+        assert!(params[1].is_pattern);
+        assert_eq!(params[1].param_type.tokens_to_string().trim(), "u8");
+    }
+
+    #[test]
+    fn test_destructuring_patterns() {
+        let input = quote! {
+            (a, b): (i32, i32),
+            Point { x, y }: Point,
+            geo::Pair(l, r): geo::Pair,
+            &c: &u8
+        };
+        let params = parse_fn_parameters(input);
+        assert_eq!(params.len(), 4);
+        assert_eq!(params[0].name.to_string(), "__arg0");
+        assert_eq!(params[0].display_name, "_0");
+        assert_eq!(params[0].pattern_text(), "( a , b )");
+        assert_eq!(
+            params[0].param_type.tokens_to_string().trim(),
+            "(i32 , i32)"
+        );
+        assert_eq!(params[1].display_name, "_1");
+        assert_eq!(params[1].pattern_text(), "Point { x , y }");
+        assert_eq!(params[2].pattern_text(), "geo :: Pair ( l , r )");
+        assert_eq!(
+            params[2].param_type.tokens_to_string().trim(),
+            "geo :: Pair"
+        );
+        assert_eq!(params[3].name.to_string(), "__arg3");
+        assert_eq!(params[3].display_name, "_3");
+        assert_eq!(params[3].pattern_text(), "& c");
+        assert!(params.iter().all(|p| p.is_pattern));
+    }
+
+    #[test]
+    fn test_no_receiver() {
+        let input = quote! { x: i32 };
+        let (receiver, params) = parse_receiver_and_parameters(input).expect("should parse");
+        assert!(receiver.is_none());
+        assert_eq!(params.len(), 1);
+    }
+
+    #[test]
+    fn test_value_receivers() {
+        let (receiver, params) =
+            parse_receiver_and_parameters(quote! { self }).expect("should parse");
+        assert!(matches!(receiver, Some(Receiver::Value { .. })));
+        assert!(params.is_empty());
+
+        let (receiver, params) =
+            parse_receiver_and_parameters(quote! { mut self, x: u8 }).expect("should parse");
+        assert!(matches!(
+            receiver,
+            Some(Receiver::Value {
+                mutability: Some(_),
+                ..
+            })
+        ));
+        assert_eq!(params.len(), 1);
+        assert_eq!(params[0].name.to_string(), "x");
+    }
+
+    #[test]
+    fn test_reference_receivers() {
+        let (receiver, params) =
+            parse_receiver_and_parameters(quote! { &self, x: u8, y: u8 }).expect("should parse");
+        let receiver = receiver.expect("should parse");
+        assert!(matches!(receiver, Receiver::Ref { .. }));
+        assert!(!receiver.is_ref_mut());
+        assert_eq!(params.len(), 2);
+
+        let (receiver, _) =
+            parse_receiver_and_parameters(quote! { &'a mut self }).expect("should parse");
+        let receiver = receiver.expect("should parse");
+        assert!(matches!(
+            receiver,
+            Receiver::Ref {
+                lifetime: Some(_),
+                ..
+            }
+        ));
+        assert!(receiver.is_ref_mut());
+    }
+
+    #[test]
+    fn test_typed_receiver() {
+        let (receiver, params) =
+            parse_receiver_and_parameters(quote! { self: Box<Self>, n: usize })
+                .expect("should parse");
+        match receiver {
+            Some(Receiver::Typed { self_type, .. }) => {
+                assert_eq!(self_type.tokens_to_string().trim(), "Box < Self >");
+            }
+            _ => panic!("expected a typed receiver"),
+        }
+        assert_eq!(params.len(), 1);
+        assert_eq!(params[0].name.to_string(), "n");
+    }
+
+    #[test]
+    fn test_invalid_parameter_is_error() {
+        let err = parse_receiver_and_parameters(quote! { x: i32, 42 })
+            .err()
+            .expect("should fail");
+        assert_eq!(err.message, "failed to parse parameters: unexpected `42`");
+    }
+
+    #[test]
+    fn test_late_self_is_error() {
+        let err = parse_receiver_and_parameters(quote! { x: i32, &self })
+            .err()
+            .expect("should fail");
+        assert_eq!(err.message, "`self` is only allowed as the first parameter");
+    }
+
+    #[test]
+    fn test_param_kinds() {
+        let input =
+            quote! { a: u8, b: &str, c: &'a mut Vec<u8>, d: impl Fn(), e: &&u8, f: Box<&u8> };
+        let params = parse_fn_parameters(input);
+        let kinds: Vec<_> = params.iter().map(|p| p.kind()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ParamKind::Owned,
+                ParamKind::Ref,
+                ParamKind::RefMut,
+                ParamKind::ImplTrait,
+                ParamKind::Ref,
+                ParamKind::Owned
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nested_impl_trait() {
+        let params = parse_fn_parameters(quote! { a: Vec<impl Clone>, b: (u8, &impl Send), c: u8 });
+        assert!(params[0].has_impl_trait());
+        assert!(params[1].has_impl_trait());
+        assert_eq!(params[1].kind(), ParamKind::Owned);
+        assert!(!params[2].has_impl_trait());
+    }
+
+    #[test]
+    fn test_name_impl_traits() {
+        let mut next = 0;
+        let (ty, named) = name_impl_traits(quote! { impl Fn(u8) -> Vec<u8> + Send }, &mut next);
+        assert_eq!(ty.to_string(), "__Impl0");
+        assert_eq!(named[0].1.to_string(), "Fn (u8) -> Vec < u8 > + Send");
+
+        let (ty, named) = name_impl_traits(
+            quote! { (&mut Vec<impl Into<String>>, impl Clone) },
+            &mut next,
+        );
+        assert_eq!(ty.to_string(), "(& mut Vec < __Impl1 > , __Impl2)");
+        let bounds: Vec<_> = named
+            .iter()
+            .map(|(param, bounds)| format!("{param}: {bounds}"))
+            .collect();
+        assert_eq!(bounds, ["__Impl1: Into < String >", "__Impl2: Clone"]);
+        assert_eq!(next, 3);
+    }
+
+    #[test]
+    fn test_replace_self() {
+        let self_type = quote! { Wrapper<T> };
+        let replaced = replace_self(
+            quote! { type_name::<Self>(); Vec<Self::Item>; SelfType },
+            &self_type,
+        );
+        assert_eq!(
+            replaced.to_string().replace(' ', ""),
+            "type_name::<Wrapper<T>>();Vec<<Wrapper<T>>::Item>;SelfType"
+        );
+    }
+
+    #[test]
+    fn test_type_string_spacing() {
+        let params = parse_fn_parameters(
+            quote! { a: Vec<Vec<u8>>, b: <I as Iterator>::Item, c: fn() -> u8 },
+        );
+        assert_eq!(params[0].type_string(), "Vec < Vec < u8 > >");
+        assert_eq!(params[1].type_string(), "< I as Iterator > :: Item");
+        assert_eq!(params[2].type_string(), "fn () -> u8");
+    }
+
+    #[test]
+    fn test_parameter_attributes() {
+        let params = parse_fn_parameters(quote! {
+            #[facet(default = 3)] retries: u32,
+            #[allow(unused_mut)] #[facet(default)] mut verbose: bool,
+            name: &str
+        });
+        assert_eq!(params.len(), 3);
+        assert_eq!(params[0].name.to_string(), "retries");
+        assert_eq!(params[0].default.as_ref().unwrap().source, "3");
+        assert!(params[0].attributes.is_empty());
+        assert_eq!(params[1].name.to_string(), "verbose");
+        assert_eq!(
+            params[1].default.as_ref().unwrap().source,
+            "Default::default()"
+        );
+        assert_eq!(params[1].attributes.len(), 1);
+        assert!(params[2].default.is_none());
+
+        let Err(err) = parse_receiver_and_parameters(quote! { #[cfg(test)] x: u8 }) else {
+            panic!("should fail");
+        };
+        assert_eq!(
+            err.message,
+            "#[facet_fn] does not support `cfg` on parameters"
+        );
+        assert!(parse_receiver_and_parameters(quote! { #[facet(skip)] x: u8 }).is_err());
+    }
+
+    #[test]
+    fn test_strip_facet_attributes() {
+        let stripped = strip_facet_attributes(quote! {
+            &self, #[facet(default = 3)] n: u32, #[allow(unused)] m: [u8; 2]
+        });
+        assert_eq!(
+            stripped.to_string(),
+            quote! { &self, n: u32, #[allow(unused)] m: [u8; 2] }.to_string()
+        );
+    }
 }
@@ -0,0 +1,146 @@
+use unsynn::*;
+
+keyword! {
+    /// The "pub" keyword.
+    pub KPub = "pub";
+    /// The "const" keyword.
+    pub KConst = "const";
+    /// The "async" keyword.
+    pub KAsync = "async";
+    /// The "unsafe" keyword.
+    pub KUnsafe = "unsafe";
+    /// The "extern" keyword.
+    pub KExtern = "extern";
+}
+
+unsynn! {
+    /// A visibility modifier: `pub`, `pub(crate)`, `pub(super)` or `pub(in path)`
+    pub struct Visibility {
+        /// The "pub" keyword
+        pub _pub: KPub,
+        /// Optional restriction in parentheses
+        pub restriction: Option<ParenthesisGroup>,
+    }
+
+    /// An ABI declaration: `extern` or `extern "C"`
+    pub struct Abi {
+        /// The "extern" keyword
+        pub _extern: KExtern,
+        /// Optional ABI name as a string literal
+        pub name: Option<LiteralString>,
+    }
+
+    /// Function qualifiers, in the order Rust requires them
+    pub struct FnQualifiers {
+        /// Optional "const" keyword
+        pub constness: Option<KConst>,
+        /// Optional "async" keyword
+        pub asyncness: Option<KAsync>,
+        /// Optional "unsafe" keyword
+        pub unsafety: Option<KUnsafe>,
+        /// Optional ABI
+        pub abi: Option<Abi>,
+    }
+}
+
+impl Visibility {
+    /// The visibility as written, e.g. `pub(crate)`
+    pub fn display(&self) -> String {
+        match &self.restriction {
+            Some(restriction) => {
+                let inner = restriction.0.stream().to_string().replace(" :: ", "::");
+                format!("pub({})", inner)
+            }
+            None => "pub".to_string(),
+        }
+    }
+}
+
+impl FnQualifiers {
+    /// Whether the function is declared `const`
+    pub fn is_const(&self) -> bool {
+        self.constness.is_some()
+    }
+
+    /// Whether the function is declared `async`
+    pub fn is_async(&self) -> bool {
+        self.asyncness.is_some()
+    }
+
+    /// Whether the function is declared `unsafe`
+    pub fn is_unsafe(&self) -> bool {
+        self.unsafety.is_some()
+    }
+
+    /// The ABI name, `"C"` for a bare `extern`
+    pub fn abi_name(&self) -> Option<String> {
+        self.abi.as_ref().map(|abi| {
+            abi.name
+                .as_ref()
+                .map_or_else(|| "C".to_string(), |name| name.as_str().to_string())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proc_macro2::TokenStream;
+    use quote::quote;
+
+    fn parse_visibility(input: TokenStream) -> Option<Visibility> {
+        let mut it = input.to_token_iter();
+        it.parse::<Option<Visibility>>()
+            .expect("option always parses")
+    }
+
+    fn parse_qualifiers(input: TokenStream) -> FnQualifiers {
+        let mut it = input.to_token_iter();
+        it.parse::<FnQualifiers>().expect("should parse")
+    }
+
+    #[test]
+    fn test_no_visibility() {
+        assert!(parse_visibility(quote! { fn }).is_none());
+    }
+
+    #[test]
+    fn test_pub_visibility() {
+        let vis = parse_visibility(quote! { pub fn }).expect("should parse");
+        assert_eq!(vis.display(), "pub");
+    }
+
+    #[test]
+    fn test_restricted_visibility() {
+        let vis = parse_visibility(quote! { pub(crate) fn }).expect("should parse");
+        assert_eq!(vis.display(), "pub(crate)");
+
+        let vis = parse_visibility(quote! { pub(in crate::a) fn }).expect("should parse");
+        assert_eq!(vis.display(), "pub(in crate::a)");
+    }
+
+    #[test]
+    fn test_no_qualifiers() {
+        let quals = parse_qualifiers(quote! { fn });
+        assert!(!quals.is_const());
+        assert!(!quals.is_async());
+        assert!(!quals.is_unsafe());
+        assert!(quals.abi_name().is_none());
+    }
+
+    #[test]
+    fn test_all_qualifiers() {
+        let quals = parse_qualifiers(quote! { const unsafe extern "system" fn });
+        assert!(quals.is_const());
+        assert!(!quals.is_async());
+        assert!(quals.is_unsafe());
+        assert_eq!(quals.abi_name().as_deref(), Some("system"));
+    }
+
+    #[test]
+    fn test_bare_extern() {
+        let quals = parse_qualifiers(quote! { async extern fn });
+        assert!(quals.is_async());
+        assert_eq!(quals.abi_name().as_deref(), Some("C"));
+    }
+}
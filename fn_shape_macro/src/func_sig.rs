@@ -2,9 +2,13 @@ use proc_macro2::TokenStream;
 use unsynn::*;
 
 // Re-use the types from our other modules
-use crate::func_params::Parameter;
+use crate::error::{ParseError, Result, expect_end};
+use crate::func_attrs::{Attribute, collect_doc};
+use crate::func_params::{Parameter, Receiver};
+use crate::func_qualifiers::{FnQualifiers, Visibility};
 use crate::generics::GenericParams;
 use crate::ret_type::ReturnType;
+use crate::where_clause::WhereClause;
 
 keyword! {
     /// The "fn" keyword.
@@ -14,6 +18,12 @@ keyword! {
 unsynn! {
     /// A complete function signature
     pub struct FunctionSignature {
+        /// Outer attributes, including doc comments
+        pub attributes: Vec<Attribute>,
+        /// Optional visibility modifier
+        pub visibility: Option<Visibility>,
+        /// Qualifiers such as const, async, unsafe and extern
+        pub qualifiers: FnQualifiers,
         /// The "fn" keyword
         pub _fn_keyword: KFn,
         /// Function name
@@ -24,6 +34,8 @@ unsynn! {
         pub params: ParenthesisGroup,
         /// Optional return type
         pub return_type: Option<ReturnType>,
+        /// Optional where clause
+        pub where_clause: Option<WhereClause>,
         /// Function body
         pub body: BraceGroup,
     }
@@ -31,55 +43,119 @@ unsynn! {
 
 /// Parsed function signature with extracted components
 pub struct ParsedFunctionSignature {
+    pub attributes: Vec<Attribute>,
+    pub doc: String,
+    pub visibility: Option<Visibility>,
+    pub qualifiers: FnQualifiers,
     pub name: Ident,
     pub generics: Option<TokenStream>,
+    pub receiver: Option<Receiver>,
     pub parameters: Vec<Parameter>,
     pub return_type: TokenStream,
+    pub where_clause: Option<TokenStream>,
+    pub where_predicates: Vec<String>,
     pub body: TokenStream,
 }
 
+impl ParsedFunctionSignature {
+    /// Extract the components of a parsed function signature
+    pub fn from_signature(sig: FunctionSignature) -> Result<Self> {
+        // Extract parameters from the content of the parenthesis group
+        let (receiver, parameters) =
+            crate::func_params::parse_receiver_and_parameters(sig.params.0.stream())?;
+
+        // Extract generics if present
+        let generics = sig.generics.map(|g| g.to_token_stream());
+
+        // Extract return type if present
+        let return_type = sig
+            .return_type
+            .map(|rt| rt.return_type.to_token_stream())
+            .unwrap_or_else(|| quote::quote! { () });
+
+        // Extract where clause and its predicates if present
+        let where_predicates = sig
+            .where_clause
+            .as_ref()
+            .map(|wc| wc.predicate_strings())
+            .unwrap_or_default();
+        let where_clause = sig.where_clause.map(|wc| wc.tokens());
+
+        // Extract body
+        let body = sig.body.to_token_stream();
+
+        // Collect doc comments from the outer attributes
+        let doc = collect_doc(&sig.attributes);
+
+        Ok(ParsedFunctionSignature {
+            attributes: sig.attributes,
+            doc,
+            visibility: sig.visibility,
+            qualifiers: sig.qualifiers,
+            name: sig.name,
+            generics,
+            receiver,
+            parameters,
+            return_type,
+            where_clause,
+            where_predicates,
+            body,
+        })
+    }
+}
+
+unsynn! {
+    /// The keyword that introduces an item, after its attributes and visibility
+    struct ItemKind {
+        _attributes: Vec<Attribute>,
+        _visibility: Option<Visibility>,
+        keyword: Ident,
+    }
+}
+
+/// Item keywords that can never start a function, for a clearer error than a parse failure
+const NON_FUNCTION_ITEMS: &[&str] = &[
+    "struct",
+    "enum",
+    "union",
+    "trait",
+    "impl",
+    "mod",
+    "use",
+    "static",
+    "type",
+    "macro_rules",
+];
+
 /// Parse a complete function signature from TokenStream
-pub fn parse_function_signature(input: TokenStream) -> ParsedFunctionSignature {
+pub fn parse_function_signature(input: TokenStream) -> Result<ParsedFunctionSignature> {
     let mut it = input.to_token_iter();
 
     match it.parse::<FunctionSignature>() {
         Ok(sig) => {
-            // Extract parameters from the parenthesis group
-            // ParenthesisGroup contains the content, we need to get its stream
-            let params_content = {
-                let params_tokens = sig.params.to_token_stream();
-                // Remove the outer parentheses by parsing as a group and getting its stream
-                let mut it = params_tokens.to_token_iter();
-                if let Ok(TokenTree::Group(group)) = it.parse::<TokenTree>() {
-                    group.stream()
-                } else {
-                    TokenStream::new() // Empty if can't parse
-                }
-            };
-            let parameters = crate::func_params::parse_fn_parameters(params_content);
-
-            // Extract generics if present
-            let generics = sig.generics.map(|g| g.to_token_stream());
-
-            // Extract return type if present
-            let return_type = sig
-                .return_type
-                .map(|rt| rt.return_type.to_token_stream())
-                .unwrap_or_else(|| quote::quote! { () });
-
-            // Extract body
-            let body = sig.body.to_token_stream();
-
-            ParsedFunctionSignature {
-                name: sig.name,
-                generics,
-                parameters,
-                return_type,
-                body,
-            }
+            expect_end(&mut it, "function signature")?;
+            ParsedFunctionSignature::from_signature(sig)
         }
         Err(err) => {
-            panic!("Failed to parse function signature: {}", err);
+            let mut item = input.to_token_iter();
+            if let Ok(kind) = item.parse::<ItemKind>() {
+                let keyword = kind.keyword.to_string();
+                if keyword == "impl" {
+                    return Err(ParseError::new(
+                        kind.keyword.span(),
+                        "#[facet_fn] cannot be applied to an impl block; use #[facet_impl] instead",
+                    ));
+                }
+                if NON_FUNCTION_ITEMS.contains(&keyword.as_str()) {
+                    return Err(ParseError::new(
+                        kind.keyword.span(),
+                        format!(
+                            "#[facet_fn] can only be applied to functions, not `{keyword}` items"
+                        ),
+                    ));
+                }
+            }
+            Err(ParseError::from_unsynn(&err, &input, "function signature"))
         }
     }
 }
@@ -97,7 +173,7 @@ mod tests {
             }
         };
 
-        let parsed = parse_function_signature(input);
+        let parsed = parse_function_signature(input).expect("should parse");
         assert_eq!(parsed.name.to_string(), "add");
         assert!(parsed.generics.is_none());
         assert_eq!(parsed.parameters.len(), 2);
@@ -114,7 +190,7 @@ mod tests {
             }
         };
 
-        let parsed = parse_function_signature(input);
+        let parsed = parse_function_signature(input).expect("should parse");
         assert_eq!(parsed.name.to_string(), "generic_add");
         assert!(parsed.generics.is_some());
         assert_eq!(parsed.parameters.len(), 2);
@@ -129,7 +205,7 @@ mod tests {
             }
         };
 
-        let parsed = parse_function_signature(input);
+        let parsed = parse_function_signature(input).expect("should parse");
         assert_eq!(parsed.name.to_string(), "no_params");
         assert_eq!(parsed.parameters.len(), 0);
         assert_eq!(parsed.return_type.to_string().trim(), "& 'static str");
@@ -143,9 +219,139 @@ mod tests {
             }
         };
 
-        let parsed = parse_function_signature(input);
+        let parsed = parse_function_signature(input).expect("should parse");
         assert_eq!(parsed.name.to_string(), "no_return");
         assert_eq!(parsed.parameters.len(), 1);
         assert_eq!(parsed.return_type.to_string().trim(), "()");
     }
+
+    #[test]
+    fn test_where_clause() {
+        let input = quote! {
+            fn cloned<T>(x: &T) -> T
+            where
+                T: Clone,
+            {
+                x.clone()
+            }
+        };
+
+        let parsed = parse_function_signature(input).expect("should parse");
+        assert_eq!(parsed.name.to_string(), "cloned");
+        assert_eq!(parsed.return_type.to_string().trim(), "T");
+        assert!(parsed.where_clause.is_some());
+        assert_eq!(parsed.where_predicates, vec!["T : Clone"]);
+    }
+
+    #[test]
+    fn test_private_function() {
+        let input = quote! {
+            fn private(x: i32) -> i32 {
+                x
+            }
+        };
+
+        let parsed = parse_function_signature(input).expect("should parse");
+        assert!(parsed.visibility.is_none());
+        assert!(!parsed.qualifiers.is_const());
+    }
+
+    #[test]
+    fn test_visibility_and_qualifiers() {
+        let input = quote! {
+            pub(crate) const unsafe extern "C" fn qualified(x: i32) -> i32 {
+                x
+            }
+        };
+
+        let parsed = parse_function_signature(input).expect("should parse");
+        assert_eq!(parsed.name.to_string(), "qualified");
+        assert_eq!(parsed.visibility.unwrap().display(), "pub(crate)");
+        assert!(parsed.qualifiers.is_const());
+        assert!(parsed.qualifiers.is_unsafe());
+        assert_eq!(parsed.qualifiers.abi_name().as_deref(), Some("C"));
+    }
+
+    #[test]
+    fn test_async_function() {
+        let input = quote! {
+            pub async fn fetch() -> u8 {
+                0
+            }
+        };
+
+        let parsed = parse_function_signature(input).expect("should parse");
+        assert_eq!(parsed.name.to_string(), "fetch");
+        assert!(parsed.qualifiers.is_async());
+    }
+
+    #[test]
+    fn test_attributes_and_docs() {
+        let input = quote! {
+            /// Documented function.
+            #[inline]
+            #[must_use]
+            pub fn documented() -> u8 {
+                0
+            }
+        };
+
+        let parsed = parse_function_signature(input).expect("should parse");
+        assert_eq!(parsed.name.to_string(), "documented");
+        assert_eq!(parsed.attributes.len(), 3);
+        assert_eq!(parsed.doc, "Documented function.");
+    }
+
+    #[test]
+    fn test_method_receiver() {
+        let input = quote! {
+            pub fn get(&self, index: usize) -> u8 {
+                self.0[index]
+            }
+        };
+
+        let parsed = parse_function_signature(input).expect("should parse");
+        assert_eq!(parsed.name.to_string(), "get");
+        assert!(matches!(parsed.receiver, Some(Receiver::Ref { .. })));
+        assert_eq!(parsed.parameters.len(), 1);
+        assert_eq!(parsed.parameters[0].name.to_string(), "index");
+    }
+
+    #[test]
+    fn test_non_function_item_is_error() {
+        let err = parse_function_signature(quote! { pub struct Point(u8); })
+            .err()
+            .expect("should fail");
+        assert_eq!(
+            err.message,
+            "#[facet_fn] can only be applied to functions, not `struct` items"
+        );
+
+        let err = parse_function_signature(quote! { impl Point {} })
+            .err()
+            .expect("should fail");
+        assert!(err.message.contains("use #[facet_impl]"));
+    }
+
+    #[test]
+    fn test_missing_body_is_error() {
+        let err = parse_function_signature(quote! { fn declared(x: u8); })
+            .err()
+            .expect("should fail");
+        assert_eq!(
+            err.message,
+            "failed to parse function signature: unexpected `;`"
+        );
+    }
+
+    #[test]
+    fn test_trailing_tokens_are_error() {
+        let err = parse_function_signature(quote! { fn one() {} fn two() {} })
+            .err()
+            .expect("should fail");
+        assert_eq!(
+            err.message,
+            "failed to parse function signature: unexpected `fn`"
+        );
+    }
 }
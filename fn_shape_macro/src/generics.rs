@@ -2,18 +2,42 @@
 use proc_macro2::TokenStream;
 use unsynn::*;
 
+// Re-use the lifetime parser and const keyword
+use crate::func_qualifiers::KConst;
+use crate::lifetimes::Lifetime;
+
 /// Parses tokens until `C` is found on the current token tree level.
 pub type VerbatimUntil<C> = Many<Cons<Except<C>, AngleTokenTree>>;
 
 unsynn! {
     /// Parses either a `TokenTree` or `<...>` grouping (which is not a [`Group`] as far as proc-macros
-    /// are concerned).
+    /// are concerned). A `->` is kept whole so its `>` does not close the grouping.
     #[derive(Clone)]
     pub struct AngleTokenTree(
         #[allow(clippy::type_complexity)] // look,
-        pub Either<Cons<Lt, Vec<Cons<Except<Gt>, AngleTokenTree>>, Gt>, TokenTree>,
+        pub Either<Cons<Lt, Vec<Cons<Except<Gt>, AngleTokenTree>>, Gt>, RArrow, TokenTree>,
     );
 
+    /// A generic lifetime parameter with optional outlives bounds
+    pub struct LifetimeParam {
+        /// The lifetime, e.g. `'a`
+        pub lifetime: Lifetime,
+        /// Optional colon and bounds (e.g., ": 'b + 'c")
+        pub bounds: Option<Cons<Colon, VerbatimUntil<Either<Comma,Gt>>>>,
+    }
+
+    /// A const generic parameter with name and type
+    pub struct ConstParam {
+        /// The "const" keyword
+        pub _const: KConst,
+        /// Const parameter name
+        pub name: Ident,
+        /// Colon separator
+        pub _colon: Colon,
+        /// Const parameter type (e.g., "usize")
+        pub param_type: VerbatimUntil<Either<Comma,Gt>>,
+    }
+
     /// A generic type parameter with name and optional bounds
     pub struct TypeParam {
         /// Type parameter name
@@ -22,17 +46,65 @@ unsynn! {
         pub bounds: Option<Cons<Colon, VerbatimUntil<Either<Comma,Gt>>>>,
     }
 
+    /// A single generic parameter of any kind
+    pub enum GenericParam {
+        /// A lifetime parameter such as `'a`
+        Lifetime(LifetimeParam),
+        /// A const parameter such as `const N: usize`
+        Const(ConstParam),
+        /// A type parameter such as `T: Clone`
+        Type(TypeParam),
+    }
+
     /// Generic parameters with angle brackets
     pub struct GenericParams {
         /// Opening angle bracket
         pub _lt: Lt,
         /// Comma-delimited list of generic parameters
-        pub params: CommaDelimitedVec<TypeParam>,
+        pub params: CommaDelimitedVec<GenericParam>,
         /// Closing angle bracket
         pub _gt: Gt,
     }
 }
 
+impl GenericParams {
+    /// The lifetime parameters, in declaration order
+    pub fn lifetime_params(&self) -> Vec<&LifetimeParam> {
+        self.params
+            .0
+            .iter()
+            .filter_map(|delim| match &delim.value {
+                GenericParam::Lifetime(param) => Some(param),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The const parameters, in declaration order
+    pub fn const_params(&self) -> Vec<&ConstParam> {
+        self.params
+            .0
+            .iter()
+            .filter_map(|delim| match &delim.value {
+                GenericParam::Const(param) => Some(param),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The type parameters, in declaration order
+    pub fn type_params(&self) -> Vec<&TypeParam> {
+        self.params
+            .0
+            .iter()
+            .filter_map(|delim| match &delim.value {
+                GenericParam::Type(param) => Some(param),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
 /// Parse generics from TokenStream
 #[cfg(test)]
 pub fn parse_generics_for_test(input: TokenStream) -> Option<GenericParams> {
@@ -57,8 +129,8 @@ mod tests {
         let input = quote! { <T> };
         let generics = parse_generics_for_test(input).expect("should parse");
         assert_eq!(generics.params.0.len(), 1);
-        assert_eq!(generics.params.0[0].value.name.to_string(), "T");
-        assert!(generics.params.0[0].value.bounds.is_none());
+        assert_eq!(generics.type_params()[0].name.to_string(), "T");
+        assert!(generics.type_params()[0].bounds.is_none());
     }
 
     #[test]
@@ -66,9 +138,9 @@ mod tests {
         let input = quote! { <T, U, V> };
         let generics = parse_generics_for_test(input).expect("should parse");
         assert_eq!(generics.params.0.len(), 3);
-        assert_eq!(generics.params.0[0].value.name.to_string(), "T");
-        assert_eq!(generics.params.0[1].value.name.to_string(), "U");
-        assert_eq!(generics.params.0[2].value.name.to_string(), "V");
+        assert_eq!(generics.type_params()[0].name.to_string(), "T");
+        assert_eq!(generics.type_params()[1].name.to_string(), "U");
+        assert_eq!(generics.type_params()[2].name.to_string(), "V");
     }
 
     #[test]
@@ -76,10 +148,10 @@ mod tests {
         let input = quote! { <T: Clone, U: Send> };
         let generics = parse_generics_for_test(input).expect("should parse");
         assert_eq!(generics.params.0.len(), 2);
-        assert_eq!(generics.params.0[0].value.name.to_string(), "T");
-        assert!(generics.params.0[0].value.bounds.is_some());
-        assert_eq!(generics.params.0[1].value.name.to_string(), "U");
-        assert!(generics.params.0[1].value.bounds.is_some());
+        assert_eq!(generics.type_params()[0].name.to_string(), "T");
+        assert!(generics.type_params()[0].bounds.is_some());
+        assert_eq!(generics.type_params()[1].name.to_string(), "U");
+        assert!(generics.type_params()[1].bounds.is_some());
     }
 
     #[test]
@@ -87,10 +159,10 @@ mod tests {
         let input = quote! { <T: Add<Output = T>, U: Iterator<Item = String>> };
         let generics = parse_generics_for_test(input).expect("should parse");
         assert_eq!(generics.params.0.len(), 2);
-        assert_eq!(generics.params.0[0].value.name.to_string(), "T");
-        assert_eq!(generics.params.0[1].value.name.to_string(), "U");
-        assert!(generics.params.0[0].value.bounds.is_some());
-        assert!(generics.params.0[1].value.bounds.is_some());
+        assert_eq!(generics.type_params()[0].name.to_string(), "T");
+        assert_eq!(generics.type_params()[1].name.to_string(), "U");
+        assert!(generics.type_params()[0].bounds.is_some());
+        assert!(generics.type_params()[1].bounds.is_some());
     }
 
     #[test]
@@ -99,4 +171,36 @@ mod tests {
         let generics = parse_generics_for_test(input).expect("should parse");
         assert_eq!(generics.params.0.len(), 0);
     }
+
+    #[test]
+    fn test_lifetime_generics() {
+        let input = quote! { <'a, 'b: 'a, T: 'a + Clone> };
+        let generics = parse_generics_for_test(input).expect("should parse");
+        assert_eq!(generics.params.0.len(), 3);
+        let lifetimes = generics.lifetime_params();
+        assert_eq!(lifetimes.len(), 2);
+        assert_eq!(lifetimes[0].lifetime.name.to_string(), "a");
+        assert!(lifetimes[0].bounds.is_none());
+        assert_eq!(lifetimes[1].lifetime.name.to_string(), "b");
+        assert!(lifetimes[1].bounds.is_some());
+        assert_eq!(generics.type_params().len(), 1);
+        assert_eq!(generics.type_params()[0].name.to_string(), "T");
+    }
+
+    #[test]
+    fn test_const_generics() {
+        let input = quote! { <T, const N: usize, const M: Wrapper<u8, 2>> };
+        let generics = parse_generics_for_test(input).expect("should parse");
+        assert_eq!(generics.params.0.len(), 3);
+        assert_eq!(generics.type_params().len(), 1);
+        let consts = generics.const_params();
+        assert_eq!(consts.len(), 2);
+        assert_eq!(consts[0].name.to_string(), "N");
+        assert_eq!(consts[0].param_type.tokens_to_string().trim(), "usize");
+        assert_eq!(consts[1].name.to_string(), "M");
+        assert_eq!(
+            consts[1].param_type.tokens_to_string().trim(),
+            "Wrapper < u8 , 2 >"
+        );
+    }
 }
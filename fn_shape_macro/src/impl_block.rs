@@ -0,0 +1,266 @@
+use proc_macro2::{Delimiter, Group, TokenStream};
+use unsynn::*;
+
+// Re-use the types from our other modules
+use crate::error::{ParseError, Result, expect_end};
+use crate::func_attrs::Attribute;
+use crate::func_params::strip_facet_attributes;
+use crate::func_sig::{FunctionSignature, ParsedFunctionSignature};
+use crate::generics::{GenericParams, VerbatimUntil};
+use crate::where_clause::{KWhere, WhereClause};
+
+keyword! {
+    /// The "impl" keyword.
+    pub KImpl = "impl";
+    /// The "for" keyword.
+    pub KFor = "for";
+}
+
+unsynn! {
+    /// Any impl item that is not a method, kept verbatim
+    pub struct OtherItem {
+        /// Outer attributes
+        pub attributes: Vec<Attribute>,
+        /// Item tokens up to its terminator
+        pub tokens: Any<Cons<Except<Either<Semicolon, BraceGroup>>, TokenTree>>,
+        /// Trailing semicolon or brace-delimited body
+        pub terminator: Either<Semicolon, BraceGroup>,
+    }
+
+    /// An item inside an impl block
+    pub enum ImplItem {
+        /// A method or associated function
+        Method(FunctionSignature),
+        /// A const, type alias or macro invocation
+        Other(OtherItem),
+    }
+
+    /// An inherent impl block: `impl<T> Type<T> where ... { ... }`
+    pub struct ImplBlock {
+        /// Outer attributes
+        pub attributes: Vec<Attribute>,
+        /// The "impl" keyword
+        pub _impl: KImpl,
+        /// Optional generic parameters
+        pub generics: Option<GenericParams>,
+        /// The self type (everything until where clause or body)
+        pub self_type: VerbatimUntil<Either<KWhere, BraceGroup>>,
+        /// Optional where clause
+        pub where_clause: Option<WhereClause>,
+        /// The impl body in braces
+        pub body: BraceGroup,
+    }
+}
+
+/// Parsed impl block with its methods split out
+pub struct ParsedImplBlock {
+    pub attributes: Vec<Attribute>,
+    pub generics: Option<TokenStream>,
+    pub self_type: TokenStream,
+    pub where_clause: Option<TokenStream>,
+    /// Every item in order, with `#[facet_fn]` markers removed from methods
+    pub items: Vec<TokenStream>,
+    /// The methods to generate shapes for
+    pub methods: Vec<ParsedFunctionSignature>,
+    /// The arguments of each shaped method's `#[facet_fn(...)]` marker, in the order of
+    /// `methods`; empty for unmarked methods
+    pub method_args: Vec<TokenStream>,
+}
+
+impl ParsedImplBlock {
+    /// The name of the self type without its path and generic arguments, e.g. `Wrapper` for
+    /// `crate::Wrapper<T>`
+    pub fn type_name(&self) -> Option<Ident> {
+        self.self_type
+            .clone()
+            .into_iter()
+            .take_while(|t| !matches!(t, TokenTree::Punct(p) if p.as_char() == '<'))
+            .filter_map(|t| match t {
+                TokenTree::Ident(ident) => Some(ident),
+                _ => None,
+            })
+            .last()
+    }
+}
+
+/// Parse an inherent impl block from TokenStream. If any method carries a `#[facet_fn]`
+/// marker only those methods are shaped, otherwise every method is.
+pub fn parse_impl_block(input: TokenStream) -> Result<ParsedImplBlock> {
+    let mut it = input.to_token_iter();
+
+    let block = it.parse::<ImplBlock>().map_err(|err| {
+        let err = ParseError::from_unsynn(&err, &input, "impl block");
+        ParseError::new(
+            err.span,
+            format!(
+                "#[facet_impl] can only be applied to inherent impl blocks ({})",
+                err.message
+            ),
+        )
+    })?;
+    expect_end(&mut it, "impl block")?;
+
+    let self_type = block.self_type.to_token_stream();
+    if let Some(token) = self_type
+        .clone()
+        .into_iter()
+        .find(|t| matches!(t, TokenTree::Ident(ident) if ident == "for"))
+    {
+        return Err(ParseError::new(
+            token.span(),
+            "#[facet_impl] supports inherent impl blocks only, not trait impls",
+        ));
+    }
+
+    let body = block.body.0.stream();
+    let mut body_it = body.to_token_iter();
+    let items = body_it
+        .parse::<Vec<ImplItem>>()
+        .map_err(|err| ParseError::from_unsynn(&err, &body, "impl block items"))?;
+    expect_end(&mut body_it, "impl block items")?;
+
+    let is_marker = |attr: &Attribute| attr.path_last_segment().as_deref() == Some("facet_fn");
+    let any_marked = items.iter().any(|item| match item {
+        ImplItem::Method(sig) => sig.attributes.iter().any(is_marker),
+        ImplItem::Other(_) => false,
+    });
+
+    let mut item_tokens = Vec::new();
+    let mut methods = Vec::new();
+    let mut method_args = Vec::new();
+    for item in items {
+        match item {
+            ImplItem::Method(mut sig) => {
+                let marker = sig.attributes.iter().find(|attr| is_marker(attr));
+                let marked = marker.is_some();
+                let args = marker.map(Attribute::arguments).unwrap_or_default();
+                sig.attributes.retain(|attr| !is_marker(attr));
+                // Parameter attributes such as `#[facet(default)]` are not valid Rust, so the
+                // method is emitted without them and shaped with them
+                let params = sig.params.0.clone();
+                let mut stripped = Group::new(
+                    Delimiter::Parenthesis,
+                    strip_facet_attributes(params.stream()),
+                );
+                stripped.set_span(params.span());
+                sig.params = ParenthesisGroup(stripped);
+                item_tokens.push(sig.to_token_stream());
+                sig.params = ParenthesisGroup(params);
+                if marked || !any_marked {
+                    methods.push(ParsedFunctionSignature::from_signature(sig)?);
+                    method_args.push(args);
+                }
+            }
+            ImplItem::Other(other) => item_tokens.push(other.to_token_stream()),
+        }
+    }
+
+    Ok(ParsedImplBlock {
+        attributes: block.attributes,
+        generics: block.generics.map(|g| g.to_token_stream()),
+        self_type,
+        where_clause: block.where_clause.map(|wc| wc.tokens()),
+        items: item_tokens,
+        methods,
+        method_args,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    #[test]
+    fn test_simple_impl() {
+        let input = quote! {
+            impl Counter {
+                pub fn new() -> Self {
+                    Counter(0)
+                }
+
+                pub fn add(&mut self, n: u32) -> u32 {
+                    self.0 += n;
+                    self.0
+                }
+            }
+        };
+
+        let parsed = parse_impl_block(input).expect("should parse");
+        assert_eq!(parsed.self_type.to_string(), "Counter");
+        assert!(parsed.generics.is_none());
+        assert_eq!(parsed.items.len(), 2);
+        assert_eq!(parsed.methods.len(), 2);
+        assert_eq!(parsed.methods[0].name.to_string(), "new");
+        assert!(parsed.methods[0].receiver.is_none());
+        assert_eq!(parsed.methods[1].name.to_string(), "add");
+        assert!(parsed.methods[1].receiver.is_some());
+    }
+
+    #[test]
+    fn test_generic_impl_with_other_items() {
+        let input = quote! {
+            impl<T: Clone> crate::Wrapper<T> where T: Default {
+                const SIZE: usize = 1;
+                fn get(&self) -> T {
+                    self.0.clone()
+                }
+            }
+        };
+
+        let parsed = parse_impl_block(input).expect("should parse");
+        assert_eq!(parsed.self_type.to_string(), "crate :: Wrapper < T >");
+        assert_eq!(parsed.type_name().unwrap().to_string(), "Wrapper");
+        assert_eq!(parsed.generics.unwrap().to_string(), "< T : Clone >");
+        assert!(parsed.where_clause.is_some());
+        assert_eq!(parsed.items.len(), 2);
+        assert_eq!(parsed.methods.len(), 1);
+        assert_eq!(parsed.methods[0].name.to_string(), "get");
+    }
+
+    #[test]
+    fn test_marked_methods_only() {
+        let input = quote! {
+            impl Counter {
+                #[facet_fn(register = false)]
+                fn shaped(&self) {}
+
+                fn unshaped(&self) {}
+            }
+        };
+
+        let parsed = parse_impl_block(input).expect("should parse");
+        assert_eq!(parsed.items.len(), 2);
+        assert!(!parsed.items[0].to_string().contains("facet_fn"));
+        assert_eq!(parsed.methods.len(), 1);
+        assert_eq!(parsed.methods[0].name.to_string(), "shaped");
+        assert_eq!(parsed.method_args[0].to_string(), "register = false");
+    }
+
+    #[test]
+    fn test_trait_impl_rejected() {
+        let input = quote! {
+            impl Default for Counter {
+                fn default() -> Self {
+                    Counter(0)
+                }
+            }
+        };
+        let err = parse_impl_block(input).err().expect("should fail");
+        assert_eq!(
+            err.message,
+            "#[facet_impl] supports inherent impl blocks only, not trait impls"
+        );
+    }
+
+    #[test]
+    fn test_non_impl_item_rejected() {
+        let err = parse_impl_block(quote! { struct Counter(u32); })
+            .err()
+            .expect("should fail");
+        assert!(
+            err.message
+                .starts_with("#[facet_impl] can only be applied to inherent impl blocks")
+        );
+    }
+}
@@ -4,41 +4,1044 @@ use proc_macro::TokenStream;
 use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
 use quote::quote;
 
+mod attr_args;
+use attr_args::{ShapeArgs, Target};
+mod call_input;
+use call_input::{parse_call_input, parse_checked_call};
+mod error;
+use error::{ParseError, Result};
+mod func_attrs;
 mod func_body;
 mod func_params;
+use func_params::{
+    ParamKind, Receiver, contains_ident, name_impl_traits, normalize_angle_spacing, replace_self,
+};
+mod func_qualifiers;
 mod generics;
+mod impl_block;
+use impl_block::{ParsedImplBlock, parse_impl_block};
+mod lifetimes;
+use lifetimes::{name_elided, replace_lifetimes};
 mod ret_type;
+use ret_type::{ReturnKind, return_kind};
+mod where_clause;
+use where_clause::merge_where_clauses;
 
 mod func_sig;
-use func_sig::parse_function_signature;
+use func_sig::{ParsedFunctionSignature, parse_function_signature};
 
 mod fn_shape_input;
 use fn_shape_input::parse_fn_shape_input;
 
 mod type_params;
-use type_params::extract_type_params;
+use type_params::{
+    GenericArg, extract_const_params, extract_generic_args, extract_lifetime_params,
+    extract_type_params, merge_generic_params,
+};
 
 /// `#[facet_fn] fn foo(...) -> R { ... }`
+///
+/// The expansion names types from the `fn_shape` runtime crate, which re-exports this macro.
+/// `#[facet_fn(register = false)]` or `#[facet_fn(skip_registry)]` keeps the function out of
+/// the global registry. Other arguments:
+///
+/// * `name = "..."` - the name recorded in the shape, in place of the Rust identifier
+/// * `rename_all = "camelCase"` - casing of the exported parameter names: `snake_case`,
+///   `camelCase` or `kebab-case`
+/// * `tags = ["...", ...]` - tags recorded in the shape
+/// * `skip_export` - no `FOO_SHAPE` re-export; the shape is only reachable via the registry
+/// * `skip_module` - keep the function as written instead of forwarding to a hidden module
+/// * `skip_args` - no `FooArgs` struct
+/// * `skip_shape` - no shape at all, only the `FooArgs` struct
+///
+/// Free functions also get a `FooArgs` struct with a field per parameter, a builder with a
+/// `with_x` setter per parameter `x`, and a `call` method, which `call!` builds from named
+/// arguments.
 #[proc_macro_attribute]
-pub fn facet_fn(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn facet_fn(attr: TokenStream, item: TokenStream) -> TokenStream {
     // Convert to proc_macro2 for parsing
     let item2: TokenStream2 = item.into();
-    let parsed = parse_function_signature(item2);
-    generate_function_shape(parsed)
+    let expanded = ShapeArgs::default()
+        .parse(attr.into(), Target::Function)
+        .and_then(|args| Ok((args, parse_function_signature(item2.clone())?)))
+        .and_then(|(args, parsed)| {
+            if let Some(receiver) = &parsed.receiver {
+                return Err(ParseError::new(
+                    receiver.span(),
+                    "#[facet_fn] cannot expand a method on its own; put #[facet_impl] on the impl block",
+                ));
+            }
+            generate_function_shape(parsed, &args)
+        });
+    expand_or_error(expanded, item2)
+}
+
+/// `#[facet_impl] impl Type { ... }` - shape every method, or only those marked `#[facet_fn]`
+#[proc_macro_attribute]
+pub fn facet_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let item2: TokenStream2 = item.into();
+    let expanded = ShapeArgs::default()
+        .parse(attr.into(), Target::Impl)
+        .and_then(|args| Ok((args, parse_impl_block(item2.clone())?)))
+        .and_then(|(args, parsed)| generate_impl_shapes(parsed, &args));
+    expand_or_error(expanded, item2)
+}
+
+/// The expansion, or the error followed by the unchanged item so that uses of the item do
+/// not pile further errors on top
+fn expand_or_error(expanded: Result<TokenStream2>, item: TokenStream2) -> TokenStream {
+    match expanded {
+        Ok(out) => out.into(),
+        Err(err) => {
+            let error = err.to_compile_error();
+            quote! { #error #item }.into()
+        }
+    }
+}
+
+/// Name of the associated or re-exported shape constant, e.g. `ADD_SHAPE`
+fn shape_ident(fn_name: &Ident) -> Ident {
+    Ident::new(
+        &format!("{}_SHAPE", fn_name.to_string().to_uppercase()),
+        Span::call_site(),
+    )
+}
+
+/// The shape item for `parsed` named `shape_name`, with `attrs` on each item it consists of;
+/// `impl_block` is set for methods. `callee` is the path dynamic calls go through, `None`
+/// when the function cannot be called that way.
+///
+/// The shape is a constant, with `'static` for any lifetime parameters. Constants cannot be
+/// generic, so a generic function's shape is the `SHAPE` constant of a marker type with its
+/// parameters, e.g. `PickShape<'a, T, N>`, which `shape_name` aliases. A generic method's
+/// marker is named after the type too, e.g. `WrapperConvertShape<Wrapper<T>, U>`, and goes
+/// beside the impl block with the self type as its first type parameter.
+fn shape_item(
+    parsed: &ParsedFunctionSignature,
+    args: &ShapeArgs,
+    shape_name: &Ident,
+    impl_block: Option<&ParsedImplBlock>,
+    callee: Option<TokenStream2>,
+    visibility: TokenStream2,
+    attrs: TokenStream2,
+) -> Result<TokenStream2> {
+    let generics = &parsed.generics;
+    let params = &parsed.parameters;
+    let where_clause = &parsed.where_clause;
+    let where_predicates = &parsed.where_predicates;
+    let doc = &parsed.doc;
+
+    let callee = if is_dyn_callable(parsed)? {
+        callee
+    } else {
+        None
+    };
+    let callable = callee.is_some();
+
+    let names: Vec<_> = params
+        .iter()
+        .map(|p| args.param_name(&p.display_name, p.is_pattern))
+        .collect();
+    // The resolved type name is taken from a function pointer to `core::any::type_name`,
+    // which cannot name a type containing `impl Trait`
+    let param_shapes: Vec<_> = params
+        .iter()
+        .zip(&names)
+        .enumerate()
+        .map(|(index, (p, name))| {
+            let ident = if p.is_pattern {
+                p.pattern_text()
+            } else {
+                p.display_name.clone()
+            };
+            let type_name = p.type_string();
+            let rust_type = if p.has_impl_trait() {
+                quote! { None }
+            } else {
+                let ty = p.param_type_tokens();
+                quote! { Some(::fn_shape::TypeName(::core::any::type_name::<#ty>)) }
+            };
+            let kind = match p.kind() {
+                ParamKind::Owned => quote! { ::fn_shape::ParamKind::Owned },
+                ParamKind::Ref => quote! { ::fn_shape::ParamKind::Ref },
+                ParamKind::RefMut => quote! { ::fn_shape::ParamKind::RefMut },
+                ParamKind::ImplTrait => quote! { ::fn_shape::ParamKind::ImplTrait },
+            };
+            let is_pattern = p.is_pattern;
+            let facet_shape = facet_shape_field(&p.param_type_tokens());
+            let json_decode = json_decode_field(&p.param_type_tokens(), p.kind(), callable);
+            let parse = parse_field(&p.param_type_tokens(), callable);
+            // `Option` parameters without a default may be omitted too, and are then `None`
+            let is_optional = matches!(
+                return_kind(&p.param_type_tokens()),
+                ReturnKind::Option { .. }
+            );
+            let default = match &p.default {
+                Some(default) => {
+                    let source = &default.source;
+                    quote! { Some(#source) }
+                }
+                None => quote! { None },
+            };
+            let default_expr = match &p.default {
+                Some(default) => Some(default.expr.clone()),
+                None => is_optional.then(|| quote! { None }),
+            };
+            let default_value = match default_expr {
+                Some(expr) if callable => {
+                    let ty = p.param_type_tokens();
+                    quote! {
+                        Some(::fn_shape::DefaultValue(|| {
+                            let value: #ty = #expr;
+                            ::std::boxed::Box::new(value)
+                        }))
+                    }
+                }
+                _ => quote! { None },
+            };
+            quote! {
+                ::fn_shape::ParamShape {
+                    name: #name,
+                    ident: #ident,
+                    index: #index,
+                    type_name: #type_name,
+                    rust_type: #rust_type,
+                    kind: #kind,
+                    is_pattern: #is_pattern,
+                    is_optional: #is_optional,
+                    default: #default,
+                    default_value: #default_value,
+                    #facet_shape
+                    #json_decode
+                    #parse
+                }
+            }
+        })
+        .collect();
+    let arity = params.len();
+    let fn_name_str = parsed.name.to_string();
+    let name = args.name.clone().unwrap_or_else(|| fn_name_str.clone());
+    let tags = &args.tags;
+
+    let type_params = generics
+        .as_ref()
+        .map(|g| extract_type_params(g.clone()))
+        .transpose()?
+        .unwrap_or_default();
+    let lifetime_names = generics
+        .as_ref()
+        .map(|g| extract_lifetime_params(g.clone()))
+        .transpose()?
+        .unwrap_or_default();
+    let const_params: Vec<_> = generics
+        .as_ref()
+        .map(|g| extract_const_params(g.clone()))
+        .transpose()?
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, ty)| quote! { ::fn_shape::ConstParamShape { name: #name, type_name: #ty } })
+        .collect();
+    let generics_str = generics
+        .as_ref()
+        .map(|g| normalize_angle_spacing(g.clone()).to_string())
+        .unwrap_or_default();
+    let generic_params = generics
+        .as_ref()
+        .map(|g| extract_generic_args(g.clone()))
+        .transpose()?
+        .unwrap_or_default();
+    // The shape item shares the generics, so each argument renders as instantiated
+    let generic_args: Vec<_> = generic_params
+        .iter()
+        .map(|arg| match arg {
+            GenericArg::Type(name) => quote! {
+                ::fn_shape::GenericArg(|| {
+                    ::std::string::String::from(::core::any::type_name::<#name>())
+                })
+            },
+            GenericArg::Const(name, _) => quote! {
+                ::fn_shape::GenericArg(|| ::std::string::ToString::to_string(&#name))
+            },
+        })
+        .collect();
+
+    // Record the visibility and qualifiers as written on the original function
+    let visibility_str = parsed
+        .visibility
+        .as_ref()
+        .map(|v| v.display())
+        .unwrap_or_default();
+    let is_const = parsed.qualifiers.is_const();
+    let is_async = parsed.qualifiers.is_async();
+    let is_unsafe = parsed.qualifiers.is_unsafe();
+    let abi = match parsed.qualifiers.abi_name() {
+        Some(name) => quote! { Some(#name) },
+        None => quote! { None },
+    };
+
+    let receiver = match &parsed.receiver {
+        None => quote! { None },
+        Some(Receiver::Value { .. }) => quote! { Some(::fn_shape::Receiver::Value) },
+        Some(receiver @ Receiver::Ref { .. }) if receiver.is_ref_mut() => {
+            quote! { Some(::fn_shape::Receiver::RefMut) }
+        }
+        Some(Receiver::Ref { .. }) => quote! { Some(::fn_shape::Receiver::Ref) },
+        Some(Receiver::Typed { self_type, .. }) => {
+            let ty = unsynn::ToTokens::tokens_to_string(self_type);
+            quote! { Some(::fn_shape::Receiver::Typed(#ty)) }
+        }
+    };
+    let self_type = match impl_block {
+        Some(block) => {
+            let ty = unsynn::ToTokens::tokens_to_string(&block.self_type);
+            quote! { Some(#ty) }
+        }
+        None => quote! { None },
+    };
+    let ret = return_shape(&parsed.return_type, callable);
+    let call = dyn_call(parsed, callee);
+    // The fallbacks of the probes behind the optional fields
+    let probe_imports = [
+        (
+            cfg!(feature = "facet"),
+            quote! { ::fn_shape::facet_probe::NoFacet },
+        ),
+        (
+            cfg!(feature = "json"),
+            quote! { ::fn_shape::json_call::NoJson },
+        ),
+        (cfg!(feature = "repl"), quote! { ::fn_shape::repl::NoRepl }),
+    ]
+    .into_iter()
+    .filter(|(enabled, _)| *enabled)
+    .map(|(_, fallback)| quote! { use #fallback as _; });
+
+    let value = quote! {
+        {
+            #( #probe_imports )*
+            ::fn_shape::FunctionShape {
+                name: #name,
+                tags: &[ #( #tags ),* ],
+                param_count: #arity,
+                param_names: &[ #( #names ),* ],
+                params: &[ #( #param_shapes ),* ],
+                type_params: &[ #( #type_params ),* ],
+                lifetimes: &[ #( #lifetime_names ),* ],
+                const_params: &[ #( #const_params ),* ],
+                generics: #generics_str,
+                generic_args: &[ #( #generic_args ),* ],
+                where_predicates: &[ #( #where_predicates ),* ],
+                doc: #doc,
+                qualifiers: ::fn_shape::FnQualifiers {
+                    visibility: #visibility_str,
+                    is_const: #is_const,
+                    is_async: #is_async,
+                    is_unsafe: #is_unsafe,
+                    abi: #abi,
+                },
+                receiver: #receiver,
+                self_type: #self_type,
+                ret: #ret,
+                call: #call,
+                ..::fn_shape::FunctionShape::new(#fn_name_str)
+            }
+        }
+    };
+
+    let type_name = impl_block.and_then(ParsedImplBlock::type_name);
+    let described = match &type_name {
+        Some(type_name) => format!("`{type_name}::{fn_name_str}`"),
+        None => format!("`{fn_name_str}`"),
+    };
+    if generic_params.is_empty() {
+        let value = replace_lifetimes(value, &lifetime_names, "static");
+        let shape_doc = format!("The shape of {described}");
+        return Ok(quote! {
+            #attrs
+            #[doc = #shape_doc]
+            #visibility const #shape_name: ::fn_shape::FunctionShape = #value;
+        });
+    }
+    let lifetimes: Vec<_> = lifetime_names
+        .iter()
+        .map(|lifetime| lifetime_tokens(&lifetime[1..]))
+        .collect();
+    let self_param = impl_block.map(|_| quote! { __Self: ?::core::marker::Sized, });
+    let self_arg = impl_block.map(|block| {
+        let self_type = &block.self_type;
+        quote! { #self_type, }
+    });
+    let struct_params = generic_params.iter().map(|param| match param {
+        GenericArg::Type(name) => quote! { #name: ?::core::marker::Sized },
+        GenericArg::Const(name, ty) => quote! { const #name: #ty },
+    });
+    let struct_args = generic_params.iter().map(|param| match param {
+        GenericArg::Type(name) | GenericArg::Const(name, _) => name,
+    });
+    let pointers: Vec<_> = generic_params
+        .iter()
+        .filter_map(|param| match param {
+            GenericArg::Type(name) => Some(quote! { *const #name }),
+            GenericArg::Const(..) => None,
+        })
+        .collect();
+    let self_pointer = impl_block.map(|_| quote! { *const __Self, });
+    // A `&'a` to the type parameters and self type makes `T: 'a` an implied bound of the
+    // impl, as it is of the function itself
+    let pointers = quote! { ( #self_pointer #( #pointers, )* ) };
+    let phantom = quote! { ( fn() -> #pointers, #( &#lifetimes #pointers, )* ) };
+
+    let marker = marker_ident(type_name.as_ref(), &parsed.name);
+    let marker_doc =
+        format!("Marker of {described}, whose `SHAPE` is its shape for these generics");
+    let shape_doc = format!("The shape of {described} for these generic arguments");
+    let alias_doc = format!("`{marker}` under the name `fn_shape!` looks up");
+    let (impl_generics, impl_where, value, alias) = match impl_block {
+        // The marker is outside the impl block, so it takes on the block's generics and
+        // `Self` is spelled out
+        Some(block) => {
+            let merged = merge_generic_params(block.generics.iter().chain(generics))?;
+            let merged_where = merge_where_clauses(block.where_clause.iter().chain(where_clause));
+            let self_type = &block.self_type;
+            (
+                replace_self(quote! { < #( #merged ),* > }, self_type),
+                merged_where.map(|clause| replace_self(clause, self_type)),
+                replace_self(value, self_type),
+                None,
+            )
+        }
+        None => (
+            generics.clone().unwrap_or_default(),
+            where_clause.clone(),
+            value,
+            Some(quote! {
+                #attrs
+                #[doc = #alias_doc]
+                #visibility use #marker as #shape_name;
+            }),
+        ),
+    };
+    Ok(quote! {
+        #attrs
+        #[doc = #marker_doc]
+        #visibility struct #marker < #( #lifetimes, )* #self_param #( #struct_params ),* > (
+            ::core::marker::PhantomData<#phantom>
+        );
+
+        #attrs
+        impl #impl_generics #marker < #( #lifetimes, )* #self_arg #( #struct_args ),* >
+        #impl_where
+        {
+            #[doc = #shape_doc]
+            pub const SHAPE: ::fn_shape::FunctionShape = #value;
+        }
+
+        #alias
+    })
+}
+
+/// Whether `parsed` has type or const parameters, so that its shape is on a marker type
+fn has_generic_params(parsed: &ParsedFunctionSignature) -> Result<bool> {
+    match &parsed.generics {
+        Some(generics) => Ok(!extract_generic_args(generics.clone())?.is_empty()),
+        None => Ok(false),
+    }
+}
+
+/// Whether `parsed` can be called with type-erased arguments. `Any` needs `'static` types,
+/// so functions with type or const parameters are left out; lifetimes become `'static`.
+fn is_dyn_callable(parsed: &ParsedFunctionSignature) -> Result<bool> {
+    let return_type = &parsed.return_type;
+    Ok(!has_generic_params(parsed)?
+        && !parsed.qualifiers.is_async()
+        && !parsed.qualifiers.is_unsafe()
+        && !parsed.parameters.iter().any(|p| p.has_impl_trait())
+        && !contains_ident(return_type.clone(), "impl")
+        && return_type.to_string() != "!")
+}
+
+/// The `::fn_shape::DynCall` for a function, as a non-capturing closure so that it can name
+/// `Self` and `inner`. Every argument is checked before any is moved out, and the receiver is
+/// borrowed last so that it does not overlap the moves.
+fn dyn_call(parsed: &ParsedFunctionSignature, callee: Option<TokenStream2>) -> TokenStream2 {
+    let params = &parsed.parameters;
+    let Some(callee) = callee else {
+        return quote! { None };
+    };
+
+    let receiver = parsed.receiver.as_ref().map(|receiver| match receiver {
+        Receiver::Value { .. } => (quote! { Self }, quote! { take }),
+        receiver @ Receiver::Ref { .. } if receiver.is_ref_mut() => {
+            (quote! { Self }, quote! { borrow_mut })
+        }
+        Receiver::Ref { .. } => (quote! { Self }, quote! { borrow }),
+        Receiver::Typed { self_type, .. } => (
+            unsynn::ToTokens::to_token_stream(self_type),
+            quote! { take },
+        ),
+    });
+    let offset = usize::from(receiver.is_some());
+    let expected = params.len() + offset;
+
+    let mut checks = Vec::new();
+    if let Some((ty, _)) = &receiver {
+        checks.push(quote! { ::fn_shape::dyn_call::check::<#ty>(args, 0)?; });
+    }
+    let mut takes = Vec::new();
+    let mut call_args = Vec::new();
+    if receiver.is_some() {
+        call_args.push(quote! { receiver });
+    }
+    for (index, p) in params.iter().enumerate() {
+        let index = index + offset;
+        let ty = p.param_type_tokens();
+        let arg = Ident::new(&format!("arg{index}"), Span::call_site());
+        checks.push(quote! { ::fn_shape::dyn_call::check::<#ty>(args, #index)?; });
+        takes.push(quote! { let #arg = ::fn_shape::dyn_call::take::<#ty>(args, #index)?; });
+        call_args.push(quote! { #arg });
+    }
+    let borrow_receiver = receiver.map(|(ty, access)| {
+        quote! { let receiver = ::fn_shape::dyn_call::#access::<#ty>(args, 0)?; }
+    });
+
+    quote! {
+        Some(::fn_shape::DynCall(
+            |args: &mut [::std::boxed::Box<dyn ::core::any::Any>]|
+                -> ::fn_shape::DynResult
+            {
+                ::fn_shape::dyn_call::check_count(args, #expected)?;
+                #( #checks )*
+                #( #takes )*
+                #borrow_receiver
+                ::core::result::Result::Ok(::std::boxed::Box::new(#callee( #( #call_args ),* )))
+            }
+        ))
+    }
+}
+
+/// `Some(TypeName)` for a return type, or `None` when it contains `impl Trait` or is `!`,
+/// which `core::any::type_name` cannot name
+fn rust_type(ty: &TokenStream2) -> TokenStream2 {
+    if contains_ident(ty.clone(), "impl") || ty.to_string() == "!" {
+        quote! { None }
+    } else {
+        quote! { Some(::fn_shape::TypeName(::core::any::type_name::<#ty>)) }
+    }
+}
+
+/// The `facet_shape` field for a type, present only with the `facet` feature. Types that
+/// cannot be named get `None`; the rest are resolved by `::fn_shape::facet_probe`.
+fn facet_shape_field(ty: &TokenStream2) -> TokenStream2 {
+    if !cfg!(feature = "facet") {
+        quote! {}
+    } else if contains_ident(ty.clone(), "impl") || ty.to_string() == "!" {
+        quote! { facet_shape: None, }
+    } else {
+        quote! { facet_shape: ::fn_shape::facet_probe::FacetProbe::<#ty>::SHAPE, }
+    }
+}
+
+/// The `json_decode` or `json_encode` field for a type, present only with the `json`
+/// feature. JSON calls go through `call_dyn`, so functions without it get `None`.
+fn json_field(ty: &TokenStream2, callable: bool, field: &str, codec: &str) -> TokenStream2 {
+    let field = Ident::new(field, Span::call_site());
+    let codec = Ident::new(codec, Span::call_site());
+    if !cfg!(feature = "json") {
+        quote! {}
+    } else if !callable {
+        quote! { #field: None, }
+    } else {
+        quote! { #field: ::fn_shape::json_call::JsonProbe::<#ty>::#codec, }
+    }
+}
+
+/// The `json_decode` field for a parameter type. `&str` is decoded by leaking the text, and
+/// no other reference can be decoded.
+fn json_decode_field(ty: &TokenStream2, kind: ParamKind, callable: bool) -> TokenStream2 {
+    if !cfg!(feature = "json") {
+        quote! {}
+    } else if callable && is_str_ref(ty) {
+        quote! {
+            json_decode: Some(::fn_shape::json_call::JsonDecode(::fn_shape::json_call::decode_str)),
+        }
+    } else if matches!(kind, ParamKind::Ref | ParamKind::RefMut) {
+        quote! { json_decode: None, }
+    } else {
+        json_field(ty, callable, "json_decode", "DECODE")
+    }
+}
+
+/// Whether `ty` is `&str` or `&'a str`
+fn is_str_ref(ty: &TokenStream2) -> bool {
+    let tokens: Vec<_> = ty.clone().into_iter().map(|t| t.to_string()).collect();
+    match tokens.as_slice() {
+        [and, ty] => and == "&" && ty == "str",
+        [and, tick, _, ty] => and == "&" && tick == "'" && ty == "str",
+        _ => false,
+    }
+}
+
+/// The `parse` field for a parameter type, present only with the `repl` feature. `&str` is
+/// parsed by leaking the text and `Option<T>` with the parser of `T`.
+fn parse_field(ty: &TokenStream2, callable: bool) -> TokenStream2 {
+    if !cfg!(feature = "repl") {
+        return quote! {};
+    } else if !callable {
+        return quote! { parse: None, };
+    }
+    if is_str_ref(ty) {
+        return quote! { parse: Some(::fn_shape::repl::ParseArg(::fn_shape::repl::parse_str)), };
+    }
+    match return_kind(ty) {
+        ReturnKind::Option { some } => {
+            quote! { parse: ::fn_shape::repl::ReplProbe::<#some>::OPTION, }
+        }
+        _ => quote! { parse: ::fn_shape::repl::ReplProbe::<#ty>::PARSE, },
+    }
+}
+
+/// The `debug` field for a return type, present only with the `repl` feature
+fn debug_field(ty: &TokenStream2, callable: bool) -> TokenStream2 {
+    if !cfg!(feature = "repl") {
+        quote! {}
+    } else if !callable {
+        quote! { debug: None, }
+    } else {
+        quote! { debug: ::fn_shape::repl::ReplProbe::<#ty>::DEBUG, }
+    }
+}
+
+/// A `::fn_shape::TypeShape` for a type inside a `Result` or `Option` return type
+fn type_shape(ty: &TokenStream2) -> TokenStream2 {
+    let type_name = normalize_angle_spacing(ty.clone()).to_string();
+    let rust_type = rust_type(ty);
+    quote! { ::fn_shape::TypeShape { type_name: #type_name, rust_type: #rust_type } }
+}
+
+/// The `::fn_shape::ReturnShape` for a return type, which is `()` when the function has
+/// none
+fn return_shape(return_type: &TokenStream2, callable: bool) -> TokenStream2 {
+    let type_name = normalize_angle_spacing(return_type.clone()).to_string();
+    let is_unit = type_name == "()";
+    let rust_type = rust_type(return_type);
+    let kind = match return_kind(return_type) {
+        ReturnKind::Plain => quote! { ::fn_shape::ReturnKind::Plain },
+        ReturnKind::Result { ok, err } => {
+            let ok = type_shape(&ok);
+            let err = match err {
+                Some(err) => {
+                    let err = type_shape(&err);
+                    quote! { Some(#err) }
+                }
+                None => quote! { None },
+            };
+            quote! { ::fn_shape::ReturnKind::Result { ok: #ok, err: #err } }
+        }
+        ReturnKind::Option { some } => {
+            let some = type_shape(&some);
+            quote! { ::fn_shape::ReturnKind::Option { some: #some } }
+        }
+    };
+    let facet_shape = facet_shape_field(return_type);
+    let json_encode = json_field(return_type, callable, "json_encode", "ENCODE");
+    let debug = debug_field(return_type, callable);
+    quote! {
+        ::fn_shape::ReturnShape {
+            type_name: #type_name,
+            rust_type: #rust_type,
+            is_unit: #is_unit,
+            kind: #kind,
+            #facet_shape
+            #json_encode
+            #debug
+        }
+    }
+}
+
+/// The registry submission for a shape constant, if the `registry` feature is on and the
+/// function is not opted out. Functions with type or const parameters have no single shape
+/// to register.
+fn register_shape(
+    parsed: &ParsedFunctionSignature,
+    args: &ShapeArgs,
+    shape: TokenStream2,
+) -> Result<Option<TokenStream2>> {
+    let registered = cfg!(feature = "registry") && args.register && !has_generic_params(parsed)?;
+    Ok(registered.then(|| {
+        quote! {
+            ::fn_shape::inventory::submit! {
+                ::fn_shape::registry::Registered::new(&#shape)
+            }
+        }
+    }))
+}
+
+/// `name` in PascalCase, e.g. `FirstWord` for `first_word`
+fn pascal_case(name: &Ident) -> String {
+    name.to_string()
+        .trim_start_matches("r#")
+        .split('_')
+        .filter_map(|word| {
+            let mut chars = word.chars();
+            let first = chars.next()?;
+            Some(first.to_uppercase().chain(chars).collect::<String>())
+        })
+        .collect()
+}
+
+/// Name of the argument struct of a function, e.g. `FirstWordArgs` for `first_word`
+fn args_ident(fn_name: &Ident) -> Ident {
+    Ident::new(&format!("{}Args", pascal_case(fn_name)), fn_name.span())
+}
+
+/// Name of the marker type of a generic function's shape, e.g. `FirstWordShape` for
+/// `first_word`, or `WrapperConvertShape` for `Wrapper::convert`
+fn marker_ident(type_name: Option<&Ident>, fn_name: &Ident) -> Ident {
+    let type_name = type_name.map(pascal_case).unwrap_or_default();
+    Ident::new(
+        &format!("{type_name}{}Shape", pascal_case(fn_name)),
+        Span::call_site(),
+    )
+}
+
+/// Name of the builder setter of parameter `name`, e.g. `with_x`, which cannot clash with
+/// `build`
+fn setter_ident(name: &Ident) -> Ident {
+    let setter = name.to_string();
+    Ident::new(
+        &format!("with_{}", setter.trim_start_matches("r#")),
+        name.span(),
+    )
+}
+
+/// Name of the macro `call!` expands to for a function, e.g. `__call_first_word`
+fn call_macro_ident(fn_name: &Ident) -> Ident {
+    let name = fn_name.to_string();
+    Ident::new(
+        &format!("__call_{}", name.trim_start_matches("r#")),
+        fn_name.span(),
+    )
 }
 
-fn generate_function_shape(parsed: func_sig::ParsedFunctionSignature) -> TokenStream {
+/// A lifetime `'{name}` as tokens
+fn lifetime_tokens(name: &str) -> TokenStream2 {
+    let tick = proc_macro2::Punct::new('\'', proc_macro2::Spacing::Joint);
+    let name = Ident::new(name, Span::call_site());
+    quote! { #tick #name }
+}
+
+/// The `FooArgs` struct of a free function, its builder and its `call` method, along with
+/// the `__call_foo!` macro that `call!` goes through to check names. The struct has the
+/// function's generic parameters and a `__Impl{n}` parameter per `impl Trait`, plus a
+/// `__marker` field when some of them appear in no parameter type. Elided lifetimes in the
+/// parameter and return types become the struct's `'a`, or `'args` when the function has
+/// its own `'a`. A parameter named `build` would clash with the builder and is an error.
+fn args_struct(parsed: &ParsedFunctionSignature) -> Result<TokenStream2> {
+    let params = &parsed.parameters;
+    let fn_name = &parsed.name;
+    let fn_name_str = fn_name.to_string();
+    let args_name = args_ident(fn_name);
+    let builder_name = Ident::new(&format!("{args_name}Builder"), fn_name.span());
+    let visibility = unsynn::ToTokens::to_token_stream(&parsed.visibility);
+    let cfg_attrs: Vec<_> = parsed
+        .attributes
+        .iter()
+        .filter(|a| a.is_cfg())
+        .map(|a| a.tokens())
+        .collect();
+
+    let (lifetimes, generic_args) = match &parsed.generics {
+        Some(generics) => (
+            extract_lifetime_params(generics.clone())?,
+            extract_generic_args(generics.clone())?,
+        ),
+        None => (Vec::new(), Vec::new()),
+    };
+    let elided = if lifetimes.iter().any(|lifetime| lifetime == "'a") {
+        "args"
+    } else {
+        "a"
+    };
+
+    let names: Vec<_> = params.iter().map(|p| &p.name).collect();
+    let name_strs: Vec<_> = params.iter().map(|p| p.name.to_string()).collect();
+    let setters: Vec<_> = params.iter().map(|p| setter_ident(&p.name)).collect();
+    let mut next_impl = 0;
+    let mut impl_params = Vec::new();
+    let mut has_elided = false;
+    let mut named_elided = |tokens: TokenStream2| {
+        let named = name_elided(tokens.clone(), elided);
+        has_elided |= named.to_string() != tokens.to_string();
+        named
+    };
+    let types: Vec<_> = params
+        .iter()
+        .map(|p| {
+            let (ty, named) = name_impl_traits(p.param_type_tokens(), &mut next_impl);
+            for (param, bounds) in named {
+                impl_params.push((param, named_elided(bounds)));
+            }
+            named_elided(ty)
+        })
+        .collect();
+    let return_type = name_elided(parsed.return_type.clone(), elided);
+    let elided_lifetime = has_elided.then(|| lifetime_tokens(elided));
+
+    // Type and lifetime parameters that no field mentions go in a marker field
+    let appears = |name: &str| types.iter().any(|ty| contains_ident(ty.clone(), name));
+    let lifetime_appears = |name: &String| {
+        types.iter().any(|ty| {
+            let names = std::slice::from_ref(name);
+            replace_lifetimes(ty.clone(), names, "static").to_string() != ty.to_string()
+        })
+    };
+    let mut unused: Vec<_> = lifetimes
+        .iter()
+        .filter(|lifetime| !lifetime_appears(lifetime))
+        .map(|lifetime| {
+            let lifetime = lifetime_tokens(&lifetime[1..]);
+            quote! { &#lifetime () }
+        })
+        .collect();
+    unused.extend(generic_args.iter().filter_map(|arg| match arg {
+        GenericArg::Type(name) if !appears(&name.to_string()) => Some(quote! { *const #name }),
+        _ => None,
+    }));
+    let marker = (!unused.is_empty()).then(|| {
+        quote! { ::core::marker::PhantomData<fn() -> ( #( #unused, )* )> }
+    });
+    let marker_field = marker.as_ref().map(|marker| {
+        quote! {
+            #[doc(hidden)]
+            pub __marker: #marker,
+        }
+    });
+    let builder_marker_field = marker.as_ref().map(|marker| quote! { __marker: #marker, });
+    let marker_value = marker
+        .as_ref()
+        .map(|_| quote! { __marker: ::core::marker::PhantomData, });
+
+    // The function's generics, minus the angle brackets and any trailing comma
+    let mut declared: Vec<_> = parsed
+        .generics
+        .clone()
+        .map(|generics| generics.into_iter().collect())
+        .unwrap_or_default();
+    declared.pop();
+    if !declared.is_empty() {
+        declared.remove(0);
+    }
+    if matches!(declared.last(), Some(proc_macro2::TokenTree::Punct(p)) if p.as_char() == ',') {
+        declared.pop();
+    }
+    let declared = (!declared.is_empty()).then(|| declared.into_iter().collect::<TokenStream2>());
+    let turbofish: Vec<_> = generic_args
+        .iter()
+        .map(|arg| match arg {
+            GenericArg::Type(name) | GenericArg::Const(name, _) => name,
+        })
+        .collect();
+    let struct_params: Vec<_> = elided_lifetime
+        .iter()
+        .cloned()
+        .chain(declared)
+        .chain(
+            impl_params
+                .iter()
+                .map(|(param, bounds)| quote! { #param: #bounds }),
+        )
+        .collect();
+    let struct_args: Vec<_> = elided_lifetime
+        .iter()
+        .cloned()
+        .chain(
+            lifetimes
+                .iter()
+                .map(|lifetime| lifetime_tokens(&lifetime[1..])),
+        )
+        .chain(turbofish.iter().map(|name| quote! { #name }))
+        .chain(impl_params.iter().map(|(param, _)| quote! { #param }))
+        .collect();
+    let (struct_params, struct_args) = if struct_params.is_empty() {
+        (None, None)
+    } else {
+        (
+            Some(quote! { < #( #struct_params ),* > }),
+            Some(quote! { < #( #struct_args ),* > }),
+        )
+    };
+    let where_clause = &parsed.where_clause;
+
+    let is_async = parsed.qualifiers.is_async();
+    let is_unsafe = parsed.qualifiers.is_unsafe();
+    let turbofish = (!turbofish.is_empty()).then(|| quote! { ::< #( #turbofish ),* > });
+    let mut forward = quote! { #fn_name #turbofish ( #( self.#names ),* ) };
+    if is_async {
+        forward = quote! { #forward.await };
+    }
+    if is_unsafe {
+        forward = quote! { unsafe { #forward } };
+    }
+    let call_qualifiers = match (is_async, is_unsafe) {
+        (true, true) => quote! { async unsafe },
+        (true, false) => quote! { async },
+        (false, true) => quote! { unsafe },
+        (false, false) => quote! {},
+    };
+
+    // Unset arguments fall back to their defaults in `build`
+    let field_values: Vec<_> = params
+        .iter()
+        .map(|p| {
+            let name = &p.name;
+            let name_str = name.to_string();
+            match &p.default {
+                Some(default) => {
+                    let expr = &default.expr;
+                    quote! { self.#name.unwrap_or_else(|| #expr) }
+                }
+                None => quote! {
+                    self.#name.ok_or(::fn_shape::MissingArg {
+                        function: #fn_name_str,
+                        name: #name_str,
+                    })?
+                },
+            }
+        })
+        .collect();
+    let (defaulted, required): (Vec<_>, Vec<_>) = params.iter().partition(|p| p.default.is_some());
+    let required = required.iter().map(|p| &p.name);
+    let defaulted = defaulted.iter().map(|p| &p.name);
+    // Macros cannot be exported from a module like items, so `call!` only reaches functions
+    // of the crate it is used in
+    let call_macro = call_macro_ident(fn_name);
+    let macro_visibility = match visibility.to_string().as_str() {
+        "pub" => quote! { pub(crate) },
+        _ => visibility.clone(),
+    };
+
+    let struct_doc = format!("Arguments of [`{fn_name_str}`], for named-argument calls");
+    let builder_doc =
+        format!("Builds [`{args_name}`], checking that every argument without a default is set");
+    let call_doc = format!("Call [`{fn_name_str}`] with these arguments");
+    let new_builder_doc = format!("An [`{builder_name}`] with no argument set");
+    let field_docs: Vec<_> = name_strs
+        .iter()
+        .map(|name| format!("Argument `{name}`"))
+        .collect();
+    let setter_docs: Vec<_> = name_strs
+        .iter()
+        .map(|name| format!("Set `{name}`"))
+        .collect();
+
+    Ok(quote! {
+        #( #cfg_attrs )*
+        #[doc = #struct_doc]
+        #[allow(dead_code)]
+        #visibility struct #args_name #struct_params #where_clause {
+            #(
+                #[doc = #field_docs]
+                pub #names: #types,
+            )*
+            #marker_field
+        }
+
+        #( #cfg_attrs )*
+        #[allow(dead_code)]
+        impl #struct_params #args_name #struct_args #where_clause {
+            #[doc = #new_builder_doc]
+            pub fn builder() -> #builder_name #struct_args {
+                #builder_name {
+                    #( #names: ::core::option::Option::None, )*
+                    #marker_value
+                }
+            }
+
+            #[doc = #call_doc]
+            pub #call_qualifiers fn call(self) -> #return_type {
+                #forward
+            }
+        }
+
+        #( #cfg_attrs )*
+        #[doc = #builder_doc]
+        #[allow(dead_code)]
+        #visibility struct #builder_name #struct_params #where_clause {
+            #( #names: ::core::option::Option<#types>, )*
+            #builder_marker_field
+        }
+
+        #( #cfg_attrs )*
+        // `_x` gets the setter `with__x`
+        #[allow(dead_code, non_snake_case)]
+        impl #struct_params #builder_name #struct_args #where_clause {
+            #(
+                #[doc = #setter_docs]
+                pub fn #setters(mut self, #names: #types) -> Self {
+                    self.#names = ::core::option::Option::Some(#names);
+                    self
+                }
+            )*
+
+            /// The arguments, with defaults for those left unset, or the first unset
+            /// argument without a default
+            pub fn build(self) -> ::core::result::Result<#args_name #struct_args, ::fn_shape::MissingArg> {
+                ::core::result::Result::Ok(#args_name {
+                    #( #names: #field_values, )*
+                    #marker_value
+                })
+            }
+        }
+
+        #( #cfg_attrs )*
+        #[doc(hidden)]
+        macro_rules! #call_macro {
+            ($($args:tt)*) => {
+                ::fn_shape::__call_checked! { [ #( #required )* ] [ #( #defaulted )* ] $($args)* }
+            };
+        }
+        #( #cfg_attrs )*
+        #[doc(hidden)]
+        #[allow(unused_imports)]
+        #macro_visibility use #call_macro;
+    })
+}
+
+fn generate_function_shape(
+    parsed: ParsedFunctionSignature,
+    args: &ShapeArgs,
+) -> Result<TokenStream2> {
+    if !args.shape || !args.module {
+        return generate_unwrapped_function(parsed, args);
+    }
+    // Without the re-export the shape is only reachable through the registry, if at all
+    let unexported = (!args.export).then(|| quote! { #[allow(dead_code, unused_imports)] });
+    let shape_const = Ident::new("SHAPE", Span::call_site());
+    let shape_definition = shape_item(
+        &parsed,
+        args,
+        &shape_const,
+        None,
+        Some(quote! { inner }),
+        quote! { pub },
+        quote! { #unexported },
+    )?;
+    let registration = register_shape(&parsed, args, quote! { SHAPE })?;
+    let args_struct = args.args_struct.then(|| args_struct(&parsed)).transpose()?;
+
+    let attributes = parsed.attributes;
+    let visibility = parsed.visibility;
+    let qualifiers = parsed.qualifiers;
     let fn_name = parsed.name;
     let generics = parsed.generics;
     let params = parsed.parameters;
     let return_type = parsed.return_type;
+    let where_clause = parsed.where_clause;
     let body = parsed.body;
 
     let hidden_mod = Ident::new(&format!("__fn_shape_{}", fn_name), Span::call_site());
-    let shape_name = Ident::new(
-        &format!("{}_SHAPE", fn_name.to_string().to_uppercase()),
-        Span::call_site(),
-    );
+    let shape_name = shape_ident(&fn_name);
+    // The wrapper binds every argument to a plain ident; `inner` keeps the original patterns
     let defs: Vec<_> = params
         .iter()
         .map(|p| {
@@ -47,6 +1050,15 @@ fn generate_function_shape(parsed: func_sig::ParsedFunctionSignature) -> TokenSt
             quote! { #name: #ty }
         })
         .collect();
+    let inner_defs: Vec<_> = params
+        .iter()
+        .map(|p| {
+            let attributes = p.attributes.iter().map(|a| a.tokens());
+            let pattern = &p.pattern;
+            let ty = &p.param_type_tokens();
+            quote! { #( #attributes )* #pattern: #ty }
+        })
+        .collect();
     let idents: Vec<_> = params
         .iter()
         .map(|p| {
@@ -54,104 +1066,374 @@ fn generate_function_shape(parsed: func_sig::ParsedFunctionSignature) -> TokenSt
             quote! { #name }
         })
         .collect();
-    let types: Vec<_> = params
+
+    // Every attribute goes on the wrapper; `cfg` must also gate the hidden module and
+    // re-export, and lint levels must reach the body in `inner`. An `expect` would go
+    // unfulfilled on the wrapper, so it is only placed on `inner`.
+    let wrapper_attrs: Vec<_> = attributes
         .iter()
-        .map(|p| {
-            let ty = &p.param_type_tokens();
-            quote! { #ty }
-        })
+        .filter(|a| a.name().as_deref() != Some("expect"))
+        .map(|a| a.tokens())
         .collect();
-    let names: Vec<_> = params
+    let cfg_attrs: Vec<_> = attributes
         .iter()
-        .map(|p| p.name.to_string())
-        .collect::<Vec<_>>();
-    let arity = params.len();
-    let fn_name_str = fn_name.to_string();
+        .filter(|a| a.is_cfg())
+        .map(|a| a.tokens())
+        .collect();
+    let inner_attrs: Vec<_> = attributes
+        .iter()
+        .filter(|a| a.is_lint())
+        .map(|a| a.tokens())
+        .collect();
 
-    // Extract  type parameters for PhantomData using unsynn parsing
-    let generics_type = if let Some(ref generics_ts) = generics {
-        extract_type_params(generics_ts.clone())
-    } else {
-        quote! { () }
-    };
+    let is_async = qualifiers.is_async();
+    let is_unsafe = qualifiers.is_unsafe();
+    let visibility = unsynn::ToTokens::to_token_stream(&visibility);
+    let qualifiers = unsynn::ToTokens::to_token_stream(&qualifiers);
 
-    let shape_definition = quote! {
-        pub fn shape #generics () -> FunctionShape<( #( #types ),* ), #return_type, #generics_type> {
-            FunctionShape::new(
-                #fn_name_str,
-                #arity,
-                &[ #( #names ),* ]
-            )
+    let export = args.export.then(|| {
+        quote! {
+            #( #cfg_attrs )*
+            #visibility use #hidden_mod::SHAPE as #shape_name;
         }
-    };
+    });
+
+    // The wrapper forwards to `inner`, awaiting and entering `unsafe` as the qualifiers demand
+    let mut forward = quote! { #hidden_mod::inner( #( #idents ),* ) };
+    if is_async {
+        forward = quote! { #forward.await };
+    }
+    if is_unsafe {
+        forward = quote! { unsafe { #forward } };
+    }
 
     let out = quote! {
         // 1) Move the real implementation into a private module
+        #( #cfg_attrs )*
         #[allow(non_snake_case)]
         mod #hidden_mod {
             use super::*;
-            pub(super) fn inner #generics ( #( #defs ),* ) -> #return_type #body
-
-            #[derive(Debug, Clone)]
-            pub struct FunctionShape<Args, Ret, Generics = ()> {
-                pub name: &'static str,
-                pub param_count: usize,
-                pub param_names: &'static [&'static str],
-                _args: core::marker::PhantomData<Args>,
-                _ret: core::marker::PhantomData<Ret>,
-                _generics: core::marker::PhantomData<Generics>,
-            }
+            #( #inner_attrs )*
+            pub(super) #qualifiers fn inner #generics ( #( #inner_defs ),* ) -> #return_type #where_clause #body
 
-            impl<Args, Ret, Generics> FunctionShape<Args, Ret, Generics> {
-                pub const fn new(
-                    name: &'static str,
-                    param_count: usize,
-                    param_names: &'static [&'static str],
-                ) -> Self {
-                    Self {
-                        name,
-                        param_count,
-                        param_names,
-                        _args: core::marker::PhantomData,
-                        _ret: core::marker::PhantomData,
-                        _generics: core::marker::PhantomData,
-                    }
+            #shape_definition
+
+            #registration
+        }
+
+        // 2) Wrapper retains the exact original signature, visibility and qualifiers
+        #( #wrapper_attrs )*
+        #visibility #qualifiers fn #fn_name #generics ( #( #defs ),* ) -> #return_type
+        #where_clause
+        {
+            #forward
+        }
+
+        // 3) Re-export the shape with the function's name
+        #export
+
+        // 4) Argument struct for named-argument calls
+        #args_struct
+    };
+
+    Ok(out)
+}
+
+/// `#[facet_fn(skip_module)]` and `#[facet_fn(skip_shape)]`: the function is kept as written,
+/// minus `#[facet(...)]` on its parameters. The shape goes beside it as `FOO_SHAPE`, or into
+/// an anonymous const when it is not exported.
+fn generate_unwrapped_function(
+    parsed: ParsedFunctionSignature,
+    args: &ShapeArgs,
+) -> Result<TokenStream2> {
+    let fn_name = &parsed.name;
+    let cfg_attrs: Vec<_> = parsed
+        .attributes
+        .iter()
+        .filter(|a| a.is_cfg())
+        .map(|a| a.tokens())
+        .collect();
+    let visibility = unsynn::ToTokens::to_token_stream(&parsed.visibility);
+
+    let shape = if !args.shape {
+        None
+    } else if args.export {
+        let shape_name = shape_ident(fn_name);
+        let definition = shape_item(
+            &parsed,
+            args,
+            &shape_name,
+            None,
+            Some(quote! { #fn_name }),
+            visibility.clone(),
+            quote! { #( #cfg_attrs )* },
+        )?;
+        let registration = register_shape(&parsed, args, quote! { #shape_name })?;
+        Some(quote! {
+            #definition
+
+            #( #cfg_attrs )*
+            #registration
+        })
+    } else {
+        let shape_const = Ident::new("SHAPE", Span::call_site());
+        let definition = shape_item(
+            &parsed,
+            args,
+            &shape_const,
+            None,
+            Some(quote! { #fn_name }),
+            quote! {},
+            quote! { #[allow(dead_code, unused_imports)] },
+        )?;
+        let registration = register_shape(&parsed, args, quote! { SHAPE })?;
+        Some(quote! {
+            #( #cfg_attrs )*
+            const _: () = {
+                #definition
+
+                #registration
+            };
+        })
+    };
+    let args_struct = args.args_struct.then(|| args_struct(&parsed)).transpose()?;
+
+    let attributes = parsed.attributes.iter().map(|a| a.tokens());
+    let qualifiers = unsynn::ToTokens::to_token_stream(&parsed.qualifiers);
+    let generics = &parsed.generics;
+    let defs = parsed.parameters.iter().map(|p| {
+        let attributes = p.attributes.iter().map(|a| a.tokens());
+        let pattern = &p.pattern;
+        let ty = &p.param_type_tokens();
+        quote! { #( #attributes )* #pattern: #ty }
+    });
+    let return_type = &parsed.return_type;
+    let where_clause = &parsed.where_clause;
+    let body = &parsed.body;
+
+    Ok(quote! {
+        // 1) The function as written
+        #( #attributes )*
+        #visibility #qualifiers fn #fn_name #generics ( #( #defs ),* ) -> #return_type
+        #where_clause
+        #body
+
+        // 2) The shape and its registration
+        #shape
+
+        // 3) Argument struct for named-argument calls
+        #args_struct
+    })
+}
+
+fn generate_impl_shapes(parsed: ParsedImplBlock, args: &ShapeArgs) -> Result<TokenStream2> {
+    let attributes: Vec<_> = parsed.attributes.iter().map(|a| a.tokens()).collect();
+    let cfg_attrs: Vec<_> = parsed
+        .attributes
+        .iter()
+        .filter(|a| a.is_cfg())
+        .map(|a| a.tokens())
+        .collect();
+    let generics = &parsed.generics;
+    let self_type = &parsed.self_type;
+    let where_clause = &parsed.where_clause;
+    let items = &parsed.items;
+
+    // Methods keep their place in the original impl and the shapes go in a second impl block
+    // for the same type, apart from the marker types of generic methods
+    let mut registrations = Vec::new();
+    let mut markers = Vec::new();
+    let shapes: Vec<_> = parsed
+        .methods
+        .iter()
+        .zip(&parsed.method_args)
+        .map(|(method, marker_args)| {
+            let shape_name = shape_ident(&method.name);
+            let method_name = &method.name;
+            // `Self` of a generic impl is not `'static`, so its methods are not dynamically
+            // callable
+            let callee = parsed
+                .generics
+                .is_none()
+                .then(|| quote! { Self::#method_name });
+            // A marker's arguments override those of the impl block
+            let method_args = args.clone().parse(marker_args.clone(), Target::Method)?;
+            let method_cfg_attrs: Vec<_> = method
+                .attributes
+                .iter()
+                .filter(|a| a.is_cfg())
+                .map(|a| a.tokens())
+                .collect();
+            let definition = shape_item(
+                method,
+                &method_args,
+                &shape_name,
+                Some(&parsed),
+                callee,
+                unsynn::ToTokens::to_token_stream(&method.visibility),
+                // A generic method's marker is outside the impl block, so it needs both
+                quote! { #( #cfg_attrs )* #( #method_cfg_attrs )* },
+            )?;
+
+            if parsed.generics.is_none() {
+                let registration =
+                    register_shape(method, &method_args, quote! { <#self_type>::#shape_name })?;
+                if let Some(registration) = registration {
+                    registrations.push(quote! {
+                        #( #cfg_attrs )*
+                        #( #method_cfg_attrs )*
+                        #registration
+                    });
                 }
             }
 
-            #shape_definition
+            if has_generic_params(method)? {
+                markers.push(definition);
+                return Ok(None);
+            }
+            Ok(Some(definition))
+        })
+        .collect::<Result<_>>()?;
+
+    let out = quote! {
+        // 1) The original impl block, minus the `#[facet_fn]` markers
+        #( #attributes )*
+        impl #generics #self_type #where_clause {
+            #( #items )*
         }
 
-        // 2) Public wrapper retains the exact original signature
-        pub fn #fn_name #generics ( #( #defs ),* ) -> #return_type {
-            #hidden_mod::inner( #( #idents ),* )
+        // 2) Associated shapes, e.g. `Type::ADD_SHAPE`
+        #( #cfg_attrs )*
+        impl #generics #self_type #where_clause {
+            #( #shapes )*
         }
 
-        // 3) Re-export the shape function with function name
-        pub use #hidden_mod::shape as #shape_name;
+        // 3) Marker types of generic methods, e.g. `TypeConvertShape<Type, U>::SHAPE`
+        #( #markers )*
+
+        // 4) Registry entries for the methods
+        #( #registrations )*
     };
 
-    out.into()
+    Ok(out)
 }
 
-/// `fn_shape!(function_name)` or `fn_shape!(function_name<T>)` - Access the shape metadata for a function
+/// `fn_shape!(function_name)`, `fn_shape!(function_name<T>)` or `fn_shape!(Type::method)` -
+/// Access the shape metadata for a function. The expansion is a constant expression, so it
+/// can be used in `const` and `static` items. A generic method's shape is on a marker type
+/// beside its type, e.g. `WrapperConvertShape` for `Wrapper::<u8>::convert<u16>`, so that
+/// marker must be in scope wherever the type is named.
 #[proc_macro]
 pub fn fn_shape(input: TokenStream) -> TokenStream {
     let input2: TokenStream2 = input.into();
-    let parsed = parse_fn_shape_input(input2);
+    let parsed = match parse_fn_shape_input(input2) {
+        Ok(parsed) => parsed,
+        Err(err) => return err.to_compile_error().into(),
+    };
     let fn_name = parsed.name;
     let generic_args = parsed.generics;
 
-    // Generate the shape function name
-    let shape_name = Ident::new(
-        &format!("{}_SHAPE", fn_name.to_string().to_uppercase()),
-        Span::call_site(),
-    );
+    let type_path = parsed.type_path;
 
-    let out = if let Some(generics) = generic_args {
-        quote! { #shape_name::#generics() }
-    } else {
-        quote! { #shape_name() }
+    let shape_name = shape_ident(&fn_name);
+
+    // See `shape_item` for the form each kind of shape takes
+    let out = match (generic_args, parsed.method_type) {
+        (None, _) => quote! { #type_path #shape_name },
+        (Some(_), Some(method_type)) => {
+            let module_path = &method_type.module_path;
+            let self_type = &method_type.self_type;
+            let marker = marker_ident(Some(&method_type.name), &fn_name);
+            let args = &parsed.generic_args;
+            quote! { #module_path #marker::< #self_type, #( #args ),* >::SHAPE }
+        }
+        (Some(generics), None) => quote! { #type_path #shape_name::#generics::SHAPE },
     };
     out.into()
 }
+
+/// `call!(add, y = 3, x = 2)` - call a `#[facet_fn]` function with named arguments, in any
+/// order. A bare `x` passes the variable `x`, and arguments with a `#[facet(default)]` may be
+/// left out. Unknown, repeated and missing names are compile errors. The function must be
+/// defined in the same crate; elsewhere use `AddArgs::builder()`.
+#[proc_macro]
+pub fn call(input: TokenStream) -> TokenStream {
+    let input: TokenStream2 = input.into();
+    let parsed = match parse_call_input(input.clone()) {
+        Ok(parsed) => parsed,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    // The function's `__call_foo!` adds its parameter names and hands on to `__call_checked!`
+    let path = parsed.path;
+    let call_macro = call_macro_ident(&parsed.name);
+    quote! { #path #call_macro! { #input } }.into()
+}
+
+/// The expansion of `call!` once the function's parameter names are known: the call through
+/// the `FooArgs` builder, or an error at each unknown, repeated or missing argument.
+#[doc(hidden)]
+#[proc_macro]
+pub fn __call_checked(input: TokenStream) -> TokenStream {
+    let checked = match parse_checked_call(input.into()) {
+        Ok(parsed) => parsed,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let parsed = checked.call;
+    let fn_name = parsed.name.to_string();
+
+    let mut errors = Vec::new();
+    for (index, (name, value)) in parsed.args.iter().enumerate() {
+        let end = value
+            .as_ref()
+            .and_then(|value| value.clone().into_iter().last())
+            .map_or(name.span(), |token| token.span());
+        let known = checked.required.iter().chain(&checked.defaulted);
+        if !known.clone().any(|param| param == name) {
+            errors.push(ParseError::spanning(
+                name.span(),
+                end,
+                format!("`{fn_name}` has no argument `{name}`"),
+            ));
+        } else if parsed.args[..index]
+            .iter()
+            .any(|(earlier, _)| earlier == name)
+        {
+            errors.push(ParseError::spanning(
+                name.span(),
+                end,
+                format!("argument `{name}` given twice"),
+            ));
+        }
+    }
+    for param in &checked.required {
+        if !parsed.args.iter().any(|(name, _)| name == param) {
+            errors.push(ParseError::new(
+                parsed.name.span(),
+                format!("missing argument `{param}` of `{fn_name}`"),
+            ));
+        }
+    }
+    if !errors.is_empty() {
+        let errors = errors.iter().map(ParseError::to_compile_error);
+        return quote! { { #( #errors; )* } }.into();
+    }
+
+    let path = parsed.path;
+    let args_name = args_ident(&parsed.name);
+    let setters = parsed.args.iter().map(|(name, value)| {
+        let setter = setter_ident(name);
+        match value {
+            Some(value) => quote! { .#setter(#value) },
+            None => quote! { .#setter(#name) },
+        }
+    });
+    quote! {
+        #path #args_name::call(
+            #path #args_name::builder()
+                #( #setters )*
+                .build()
+                .expect("named arguments are checked at compile time"),
+        )
+    }
+    .into()
+}
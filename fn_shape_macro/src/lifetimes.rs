@@ -0,0 +1,177 @@
+use proc_macro2::{Delimiter, Group, Punct, Spacing, TokenStream, TokenTree};
+use unsynn::*;
+
+unsynn! {
+    /// A lifetime such as `'a` or `'_`
+    #[derive(Clone)]
+    pub struct Lifetime {
+        /// The leading tick
+        pub _tick: LifetimeTick,
+        /// Lifetime name
+        pub name: Ident,
+    }
+}
+
+/// `ty` with the elided lifetime of every reference, and every `'_`, replaced by `'{name}`.
+/// The arguments and return type of `Fn(..)` sugar and `fn(..)` pointers keep their own
+/// elision, and lifetimes hidden in paths such as `Formatter` are left alone.
+pub fn name_elided(ty: TokenStream, name: &str) -> TokenStream {
+    let lifetime = |span| {
+        [
+            TokenTree::Punct(Punct::new('\'', Spacing::Joint)),
+            TokenTree::Ident(proc_macro2::Ident::new(name, span)),
+        ]
+    };
+    let mut out = Vec::new();
+    let mut tokens = ty.into_iter().peekable();
+    // Inside the `-> R` of `Fn` sugar, until a `,` or `>` closes it
+    let mut fn_return_depth: Option<usize> = None;
+    while let Some(token) = tokens.next() {
+        if let Some(depth) = fn_return_depth.as_mut() {
+            match &token {
+                TokenTree::Punct(punct) if punct.as_char() == '<' => *depth += 1,
+                TokenTree::Punct(punct) if matches!(punct.as_char(), ',' | '>') && *depth == 0 => {
+                    fn_return_depth = None;
+                }
+                TokenTree::Punct(punct) if punct.as_char() == '>' => *depth -= 1,
+                _ => {}
+            }
+            if fn_return_depth.is_some() {
+                out.push(token);
+                continue;
+            }
+        }
+        match token {
+            TokenTree::Punct(punct) if punct.as_char() == '&' => {
+                let mut alone = Punct::new('&', Spacing::Alone);
+                alone.set_span(punct.span());
+                out.push(TokenTree::Punct(alone));
+                let named =
+                    matches!(tokens.peek(), Some(TokenTree::Punct(next)) if next.as_char() == '\'');
+                if !named {
+                    out.extend(lifetime(punct.span()));
+                }
+            }
+            TokenTree::Punct(punct) if punct.as_char() == '\'' => {
+                out.push(TokenTree::Punct(punct));
+                match tokens.next() {
+                    Some(TokenTree::Ident(ident)) if ident == "_" => {
+                        out.push(TokenTree::Ident(proc_macro2::Ident::new(
+                            name,
+                            ident.span(),
+                        )));
+                    }
+                    Some(other) => out.push(other),
+                    None => {}
+                }
+            }
+            TokenTree::Group(group) => {
+                let is_fn_sugar = group.delimiter() == Delimiter::Parenthesis
+                    && matches!(
+                        out.last(),
+                        Some(TokenTree::Ident(ident))
+                            if ["Fn", "FnMut", "FnOnce", "fn"].iter().any(|f| ident == f)
+                    );
+                if is_fn_sugar {
+                    out.push(TokenTree::Group(group));
+                    let arrow = matches!(tokens.peek(), Some(TokenTree::Punct(next)) if next.as_char() == '-');
+                    if arrow {
+                        out.extend(tokens.by_ref().take(2));
+                        fn_return_depth = Some(0);
+                    }
+                    continue;
+                }
+                let mut named = Group::new(group.delimiter(), name_elided(group.stream(), name));
+                named.set_span(group.span());
+                out.push(TokenTree::Group(named));
+            }
+            other => out.push(other),
+        }
+    }
+    out.into_iter().collect()
+}
+
+/// `tokens` with each of the lifetimes `names`, e.g. `'a`, replaced by `'{with}`
+pub fn replace_lifetimes(tokens: TokenStream, names: &[String], with: &str) -> TokenStream {
+    let mut out = Vec::new();
+    let mut tokens = tokens.into_iter();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Punct(punct) if punct.as_char() == '\'' => {
+                out.push(TokenTree::Punct(punct));
+                match tokens.next() {
+                    Some(TokenTree::Ident(ident))
+                        if names.iter().any(|name| ident == name[1..]) =>
+                    {
+                        out.push(TokenTree::Ident(proc_macro2::Ident::new(
+                            with,
+                            ident.span(),
+                        )));
+                    }
+                    Some(other) => out.push(other),
+                    None => {}
+                }
+            }
+            TokenTree::Group(group) => {
+                let mut replaced = Group::new(
+                    group.delimiter(),
+                    replace_lifetimes(group.stream(), names, with),
+                );
+                replaced.set_span(group.span());
+                out.push(TokenTree::Group(replaced));
+            }
+            other => out.push(other),
+        }
+    }
+    out.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    #[test]
+    fn test_parse_lifetime() {
+        let input = quote! { 'a };
+        let mut it = input.to_token_iter();
+        let lifetime = it.parse::<Lifetime>().expect("should parse");
+        assert_eq!(lifetime.name.to_string(), "a");
+    }
+
+    #[test]
+    fn test_name_elided() {
+        let named = |ty: TokenStream| name_elided(ty, "a").to_string();
+        assert_eq!(named(quote! { &str }), quote! { &'a str }.to_string());
+        assert_eq!(
+            named(quote! { &&mut [&u8] }),
+            quote! { &'a &'a mut [&'a u8] }.to_string()
+        );
+        assert_eq!(
+            named(quote! { Cow<'_, str> }),
+            quote! { Cow<'a, str> }.to_string()
+        );
+        assert_eq!(
+            named(quote! { (&'static str, Vec<u8>) }),
+            quote! { (&'static str, Vec<u8>) }.to_string()
+        );
+        assert_eq!(
+            named(quote! { (&dyn Fn(&str) -> &str, fn(&u8), &u8) }),
+            quote! { (&'a dyn Fn(&str) -> &str, fn(&u8), &'a u8) }.to_string()
+        );
+    }
+
+    #[test]
+    fn test_replace_lifetimes() {
+        let names = ["'a".to_string(), "'b".to_string()];
+        let replaced = replace_lifetimes(
+            quote! { (&'a str, Cow<'b, [&'c u8]>, "'a", &str) },
+            &names,
+            "static",
+        );
+        assert_eq!(
+            replaced.to_string(),
+            quote! { (&'static str, Cow<'static, [&'c u8]>, "'a", &str) }.to_string()
+        );
+    }
+}
@@ -1,7 +1,10 @@
-#[cfg(test)]
 use proc_macro2::TokenStream;
 use unsynn::*;
 
+// Re-use the where keyword so the return type stops before a where clause
+use crate::generics::VerbatimUntil as AngleVerbatimUntil;
+use crate::where_clause::KWhere;
+
 /// Parses tokens until `C` is found on the current token tree level.
 pub type VerbatimUntil<C> = Many<Cons<Except<C>, TokenTree>>;
 
@@ -10,8 +13,68 @@ unsynn! {
     pub struct ReturnType {
         /// The "->" arrow
         pub _arrow: RArrow,
-        /// Return type (everything until brace group)
-        pub return_type: VerbatimUntil<BraceGroup>,
+        /// Return type (everything until where clause or brace group)
+        pub return_type: VerbatimUntil<Either<KWhere, BraceGroup>>,
+    }
+
+    /// A generic argument of a wrapper type such as `Result`
+    pub struct WrappedArg {
+        /// Argument tokens (everything until comma or closing angle bracket)
+        pub arg: AngleVerbatimUntil<Either<Comma, Gt>>,
+    }
+
+    /// A whole type of the form `path::Name<A, B>`
+    pub struct WrappedType {
+        /// Optional leading `::`
+        pub _leading: Option<PathSep>,
+        /// Path segments before the name
+        pub _path: Any<Cons<Ident, PathSep>>,
+        /// Type name, e.g. `Result`
+        pub name: Ident,
+        /// Opening angle bracket
+        pub _lt: Lt,
+        /// Comma-delimited generic arguments
+        pub args: CommaDelimitedVec<WrappedArg>,
+        /// Closing angle bracket
+        pub _gt: Gt,
+        /// Nothing may follow the type
+        pub _end: EndOfStream,
+    }
+}
+
+/// A return type split into the parts callers handle differently
+pub enum ReturnKind {
+    /// Any other type
+    Plain,
+    /// `Result<T, E>`; the error type is `None` for aliases like `io::Result<T>`
+    Result {
+        ok: TokenStream,
+        err: Option<TokenStream>,
+    },
+    /// `Option<T>`
+    Option { some: TokenStream },
+}
+
+/// Recognise `Result` and `Option` return types by their last path segment
+pub fn return_kind(return_type: &TokenStream) -> ReturnKind {
+    let mut it = return_type.to_token_iter();
+    let Ok(wrapped) = it.parse::<WrappedType>() else {
+        return ReturnKind::Plain;
+    };
+    let mut args = wrapped
+        .args
+        .0
+        .into_iter()
+        .map(|delim| delim.value.arg.to_token_stream());
+    match (
+        wrapped.name.to_string().as_str(),
+        args.next(),
+        args.next(),
+        args.next(),
+    ) {
+        ("Result", Some(ok), err, None) => ReturnKind::Result { ok, err },
+        ("Option", Some(some), None, None) => ReturnKind::Option { some },
+        _ => ReturnKind::Plain,
     }
 }
 
@@ -72,4 +135,56 @@ mod tests {
         let ret_type = parse_return_type(input);
         assert_eq!(ret_type.to_string().trim(), "& 'static str");
     }
+
+    #[test]
+    fn test_return_type_before_where_clause() {
+        let input: Vec<TokenTree> = quote! { -> T where T: Clone { x } }.into_iter().collect();
+        let ret_type = parse_return_type(input);
+        assert_eq!(ret_type.to_string().trim(), "T");
+    }
+
+    fn kind_strings(input: TokenStream) -> (String, Vec<String>) {
+        match return_kind(&input) {
+            ReturnKind::Plain => ("plain".to_string(), vec![]),
+            ReturnKind::Result { ok, err } => (
+                "result".to_string(),
+                core::iter::once(ok)
+                    .chain(err)
+                    .map(|t| t.to_string())
+                    .collect(),
+            ),
+            ReturnKind::Option { some } => ("option".to_string(), vec![some.to_string()]),
+        }
+    }
+
+    #[test]
+    fn test_result_return_kind() {
+        assert_eq!(
+            kind_strings(quote! { Result<Vec<u8>, Box<dyn Error>> }),
+            (
+                "result".to_string(),
+                vec!["Vec < u8 >".to_string(), "Box < dyn Error >".to_string()]
+            )
+        );
+        assert_eq!(
+            kind_strings(quote! { std::io::Result<()> }),
+            ("result".to_string(), vec!["()".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_option_return_kind() {
+        assert_eq!(
+            kind_strings(quote! { ::core::option::Option<&'a str> }),
+            ("option".to_string(), vec!["& 'a str".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_plain_return_kind() {
+        assert_eq!(kind_strings(quote! { i32 }).0, "plain");
+        assert_eq!(kind_strings(quote! { Vec<Option<u8>> }).0, "plain");
+        assert_eq!(kind_strings(quote! { Option<u8>::Item }).0, "plain");
+        assert_eq!(kind_strings(quote! { Option<u8, u8> }).0, "plain");
+    }
 }
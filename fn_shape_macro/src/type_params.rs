@@ -2,36 +2,83 @@ use proc_macro2::TokenStream;
 use unsynn::*;
 
 // Re-use the generics parser
-use crate::generics::GenericParams;
+use crate::error::{ParseError, Result};
+use crate::generics::{GenericParam, GenericParams};
 
-/// Extract just the type parameter names from generic parameters
-/// Returns a TokenStream suitable for PhantomData<(A, B, C)>
-pub fn extract_type_params(generics_ts: TokenStream) -> TokenStream {
+/// Parse generic parameters that were already accepted by the signature parser
+fn parse_generic_params(generics_ts: TokenStream) -> Result<GenericParams> {
     let mut it = generics_ts.to_token_iter();
+    it.parse::<GenericParams>()
+        .map_err(|err| ParseError::from_unsynn(&err, &generics_ts, "generic parameters"))
+}
+
+/// Extract just the type parameter names from generic parameters
+pub fn extract_type_params(generics_ts: TokenStream) -> Result<Vec<String>> {
+    Ok(parse_generic_params(generics_ts)?
+        .type_params()
+        .into_iter()
+        .map(|param| param.name.to_string())
+        .collect())
+}
+
+/// Extract the lifetime parameter names from generic parameters, e.g. `'a`
+pub fn extract_lifetime_params(generics_ts: TokenStream) -> Result<Vec<String>> {
+    Ok(parse_generic_params(generics_ts)?
+        .lifetime_params()
+        .into_iter()
+        .map(|param| format!("'{}", param.lifetime.name))
+        .collect())
+}
+
+/// Extract the const parameter names and types from generic parameters
+pub fn extract_const_params(generics_ts: TokenStream) -> Result<Vec<(String, String)>> {
+    Ok(parse_generic_params(generics_ts)?
+        .const_params()
+        .into_iter()
+        .map(|param| (param.name.to_string(), param.param_type.tokens_to_string()))
+        .collect())
+}
 
-    match it.parse::<GenericParams>() {
-        Ok(generics) => {
-            let type_param_names: Vec<_> = generics
-                .params
-                .0
-                .into_iter()
-                .map(|delim| delim.value.name)
-                .collect();
-
-            if type_param_names.is_empty() {
-                quote::quote! { () }
-            } else if type_param_names.len() == 1 {
-                let param = &type_param_names[0];
-                quote::quote! { #param }
-            } else {
-                quote::quote! { ( #( #type_param_names ),* ) }
+/// A type parameter by name, or a const parameter by name and type
+pub enum GenericArg {
+    Type(Ident),
+    Const(Ident, TokenStream),
+}
+
+/// Extract the type and const parameters in declaration order, as a turbofish lists them
+pub fn extract_generic_args(generics_ts: TokenStream) -> Result<Vec<GenericArg>> {
+    Ok(parse_generic_params(generics_ts)?
+        .params
+        .0
+        .into_iter()
+        .filter_map(|delim| match delim.value {
+            GenericParam::Lifetime(_) => None,
+            GenericParam::Const(param) => Some(GenericArg::Const(
+                param.name,
+                param.param_type.to_token_stream(),
+            )),
+            GenericParam::Type(param) => Some(GenericArg::Type(param.name)),
+        })
+        .collect())
+}
+
+/// The parameters of several generic parameter lists, e.g. an impl block's and a method's, as
+/// one list with their bounds; the lifetimes go first, as Rust requires
+pub fn merge_generic_params<'a>(
+    lists: impl IntoIterator<Item = &'a TokenStream>,
+) -> Result<Vec<TokenStream>> {
+    let mut lifetimes = Vec::new();
+    let mut others = Vec::new();
+    for list in lists {
+        for delim in parse_generic_params(list.clone())?.params.0 {
+            match delim.value {
+                param @ GenericParam::Lifetime(_) => lifetimes.push(param.to_token_stream()),
+                param => others.push(param.to_token_stream()),
             }
         }
-        Err(_) => {
-            // Fallback to unit type if parsing fails
-            quote::quote! { () }
-        }
     }
+    lifetimes.extend(others);
+    Ok(lifetimes)
 }
 
 #[cfg(test)]
@@ -42,28 +89,107 @@ mod tests {
     #[test]
     fn test_single_type_param() {
         let input = quote! { <T> };
-        let result = extract_type_params(input);
-        assert_eq!(result.to_string().trim(), "T");
+        let result = extract_type_params(input).expect("should parse");
+        assert_eq!(result, vec!["T"]);
     }
 
     #[test]
     fn test_multiple_type_params() {
         let input = quote! { <A, B, C> };
-        let result = extract_type_params(input);
-        assert_eq!(result.to_string().trim(), "(A , B , C)");
+        let result = extract_type_params(input).expect("should parse");
+        assert_eq!(result, vec!["A", "B", "C"]);
     }
 
     #[test]
     fn test_type_params_with_bounds() {
         let input = quote! { <T: Clone, U: Send> };
-        let result = extract_type_params(input);
-        assert_eq!(result.to_string().trim(), "(T , U)");
+        let result = extract_type_params(input).expect("should parse");
+        assert_eq!(result, vec!["T", "U"]);
+    }
+
+    #[test]
+    fn test_lifetimes_excluded() {
+        let input = quote! { <'a, T: 'a> };
+        let result = extract_type_params(input).expect("should parse");
+        assert_eq!(result, vec!["T"]);
+    }
+
+    #[test]
+    fn test_lifetime_params() {
+        let input = quote! { <'a, 'b: 'a, T> };
+        assert_eq!(
+            extract_lifetime_params(input).expect("should parse"),
+            vec!["'a", "'b"]
+        );
+        assert!(
+            extract_lifetime_params(quote! { <T> })
+                .expect("should parse")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_consts_excluded() {
+        let input = quote! { <T, const N: usize> };
+        let result = extract_type_params(input).expect("should parse");
+        assert_eq!(result, vec!["T"]);
+
+        let input = quote! { <const N: usize> };
+        let result = extract_type_params(input).expect("should parse");
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn test_const_params() {
+        let input = quote! { <T, const N: usize, const FLAG: bool> };
+        assert_eq!(
+            extract_const_params(input).expect("should parse"),
+            vec![
+                ("N".to_string(), "usize".to_string()),
+                ("FLAG".to_string(), "bool".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generic_args() {
+        let input = quote! { <'a, T: Iterator<Item = &'a u8>, const N: usize, U> };
+        let names: Vec<_> = extract_generic_args(input)
+            .expect("should parse")
+            .into_iter()
+            .map(|arg| match arg {
+                GenericArg::Type(name) => format!("type {name}"),
+                GenericArg::Const(name, ty) => format!("const {name}: {ty}"),
+            })
+            .collect();
+        assert_eq!(names, vec!["type T", "const N: usize", "type U"]);
+    }
+
+    #[test]
+    fn test_merge_generic_params() {
+        let impl_generics = quote! { <'a, T: Clone> };
+        let method_generics = quote! { <U, 'b: 'a, const N: usize> };
+        let merged: Vec<_> = merge_generic_params([&impl_generics, &method_generics])
+            .expect("should parse")
+            .into_iter()
+            .map(|param| param.to_string())
+            .collect();
+        assert_eq!(
+            merged,
+            vec!["'a", "'b : 'a", "T : Clone", "U", "const N : usize"]
+        );
     }
 
     #[test]
     fn test_empty_generics() {
         let input = quote! { <> };
-        let result = extract_type_params(input);
-        assert_eq!(result.to_string().trim(), "()");
+        let result = extract_type_params(input).expect("should parse");
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_generics_is_error() {
+        let input = quote! { <T: Clone };
+        assert!(extract_type_params(input).is_err());
     }
 }
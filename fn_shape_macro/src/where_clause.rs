@@ -0,0 +1,107 @@
+use proc_macro2::TokenStream;
+use unsynn::*;
+
+// Re-use the angle-bracket-aware token parser
+use crate::generics::VerbatimUntil;
+
+keyword! {
+    /// The "where" keyword.
+    pub KWhere = "where";
+}
+
+unsynn! {
+    /// A single where predicate, e.g. `T: Add<Output = T>`
+    pub struct WherePredicate {
+        /// Predicate tokens (everything until comma or function body)
+        pub predicate: VerbatimUntil<Either<Comma, BraceGroup>>,
+    }
+
+    /// A where clause before the function body
+    pub struct WhereClause {
+        /// The "where" keyword
+        pub _where: KWhere,
+        /// Comma-delimited list of predicates
+        pub predicates: CommaDelimitedVec<WherePredicate>,
+    }
+}
+
+impl WhereClause {
+    /// The predicates as source strings, one per bound
+    pub fn predicate_strings(&self) -> Vec<String> {
+        self.predicates
+            .0
+            .iter()
+            .map(|delim| delim.value.predicate.tokens_to_string())
+            .collect()
+    }
+
+    /// Convert the where clause to TokenStream for use with quote!
+    pub fn tokens(&self) -> TokenStream {
+        self.to_token_stream()
+    }
+}
+
+/// The predicates of several where clauses, e.g. an impl block's and a method's, as one
+/// clause; `None` when there are none
+pub fn merge_where_clauses<'a>(
+    clauses: impl IntoIterator<Item = &'a TokenStream>,
+) -> Option<TokenStream> {
+    let predicates: Vec<TokenStream> = clauses
+        .into_iter()
+        .filter_map(|clause| {
+            // Drop the `where` and any trailing comma
+            let mut tokens: Vec<_> = clause.clone().into_iter().skip(1).collect();
+            if matches!(tokens.last(), Some(TokenTree::Punct(p)) if p.as_char() == ',') {
+                tokens.pop();
+            }
+            (!tokens.is_empty()).then(|| tokens.into_iter().collect())
+        })
+        .collect();
+    (!predicates.is_empty()).then(|| quote::quote! { where #( #predicates ),* })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    fn parse_where_clause(input: TokenStream) -> Option<WhereClause> {
+        let mut it = input.to_token_iter();
+        it.parse::<WhereClause>().ok()
+    }
+
+    #[test]
+    fn test_no_where_clause() {
+        assert!(parse_where_clause(quote! { { x } }).is_none());
+    }
+
+    #[test]
+    fn test_single_predicate() {
+        let clause = parse_where_clause(quote! { where T: Clone { x } }).expect("should parse");
+        assert_eq!(clause.predicate_strings(), vec!["T : Clone"]);
+    }
+
+    #[test]
+    fn test_multiple_predicates() {
+        let clause = parse_where_clause(quote! {
+            where T: Add<Output = T>, U: Into<HashMap<String, T>>, { x }
+        })
+        .expect("should parse");
+        assert_eq!(
+            clause.predicate_strings(),
+            vec![
+                "T : Add < Output = T >",
+                "U : Into < HashMap < String , T > >"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_where_clauses() {
+        let impl_where = quote! { where T: Clone, };
+        let method_where = quote! { where U: Into<T> };
+        let merged = merge_where_clauses([&impl_where, &method_where]).expect("has predicates");
+        assert_eq!(merged.to_string(), "where T : Clone , U : Into < T >");
+        assert!(merge_where_clauses([]).is_none());
+    }
+}
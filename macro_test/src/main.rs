@@ -1,7 +1,15 @@
+use core::future::Future;
 use core::ops::Add;
-use fn_shape_macro::{facet_fn, fn_shape};
+use core::task::{Context, Poll, Waker};
+use fn_shape::{facet_fn, facet_impl, fn_shape};
+use std::collections::HashMap;
 
+/// Adds two numbers.
+///
+/// Overflow wraps in release builds.
 #[facet_fn]
+#[inline]
+#[must_use]
 fn add(x: i32, y: i32) -> i32 {
     x + y
 }
@@ -21,6 +29,182 @@ fn generic_add<T: Add<Output = T>>(x: T, y: T) -> T {
     x + y
 }
 
+#[facet_fn]
+fn sum_where<T>(x: T, y: T) -> T
+where
+    T: Add<Output = T> + Copy,
+{
+    x + y
+}
+
+#[facet_fn]
+fn first_word<'a>(text: &'a str) -> &'a str {
+    text.split_whitespace().next().unwrap_or("")
+}
+
+#[facet_fn]
+fn str_len(text: &str) -> usize {
+    text.len()
+}
+
+#[facet_fn]
+fn last_item<T>(items: &[T]) -> Option<&T> {
+    items.last()
+}
+
+#[facet_fn]
+fn checksum<const N: usize>(bytes: [u8; N]) -> u32 {
+    bytes.iter().map(|&b| u32::from(b)).sum()
+}
+
+#[facet_fn]
+fn lookup(scores: HashMap<String, i32>, key: Result<String, String>) -> Option<i32> {
+    key.ok().and_then(|k| scores.get(&k).copied())
+}
+
+#[facet_fn]
+fn apply_twice(f: impl Fn(i32, i32) -> i32, x: i32) -> i32 {
+    f(f(x, x), x)
+}
+
+#[facet_fn]
+fn first_item<I: Iterator>(iter: I, fallback: <I as Iterator>::Item) -> <I as Iterator>::Item {
+    let mut iter = iter;
+    iter.next().unwrap_or(fallback)
+}
+
+#[facet_fn]
+fn countdown(mut from: u32, _: bool, (step, floor): (u32, u32)) -> Vec<u32> {
+    let mut out = vec![from];
+    while from >= floor + step {
+        from -= step;
+        out.push(from);
+    }
+    out
+}
+
+#[facet_fn]
+pub(crate) const fn double(x: u32) -> u32 {
+    x * 2
+}
+
+#[facet_fn]
+async fn answer() -> u8 {
+    42
+}
+
+#[facet_fn]
+unsafe extern "C" fn read_first(ptr: *const u8) -> u8 {
+    unsafe { *ptr }
+}
+
+#[facet_fn]
+#[allow(unused_variables)]
+fn ignore_input(input: u8) {}
+
+#[facet_fn]
+fn pad(
+    text: &str,
+    #[facet(default = 6)] width: usize,
+    #[facet(default)] fill: Option<char>,
+) -> String {
+    let fill = fill.unwrap_or('.').to_string();
+    format!("{text}{}", fill.repeat(width.saturating_sub(text.len())))
+}
+
+#[facet_fn]
+#[cfg(test)]
+fn only_in_tests() -> bool {
+    true
+}
+
+#[derive(Debug)]
+struct Counter(u32);
+
+#[facet_impl]
+impl Counter {
+    const START: u32 = 0;
+
+    /// Creates a counter at zero.
+    pub fn new() -> Self {
+        Counter(Self::START)
+    }
+
+    pub fn get(&self) -> u32 {
+        self.0
+    }
+
+    pub fn add(&mut self, #[facet(default = 1)] n: u32) -> u32 {
+        self.0 += n;
+        self.0
+    }
+
+    fn into_inner(self) -> u32 {
+        self.0
+    }
+
+    #[allow(clippy::boxed_local)]
+    fn boxed_get(self: Box<Self>) -> u32 {
+        self.0
+    }
+}
+
+struct Wrapper<T>(T);
+
+#[facet_impl]
+impl<T: Clone> Wrapper<T> {
+    #[facet_fn]
+    fn convert<U: From<T>>(&self) -> U {
+        U::from(self.0.clone())
+    }
+
+    #[facet_fn]
+    fn inner(&self) -> T {
+        self.0.clone()
+    }
+
+    #[facet_fn]
+    fn pair<'a, V>(&'a self, other: &'a V) -> (&'a Self, &'a V) {
+        (self, other)
+    }
+
+    fn unshaped(&self) -> T {
+        self.0.clone()
+    }
+}
+
+#[derive(Default)]
+struct Thermostat {
+    target: f32,
+}
+
+#[facet_impl(rename_all = "camelCase", tags = ["climate"])]
+impl Thermostat {
+    #[facet_fn(name = "setTarget")]
+    fn set_target(&mut self, target_celsius: f32) {
+        self.target = target_celsius;
+    }
+
+    #[facet_fn(rename_all = "snake_case", tags = [])]
+    fn target_in(&self, in_fahrenheit: bool) -> f32 {
+        if in_fahrenheit {
+            self.target * 1.8 + 32.0
+        } else {
+            self.target
+        }
+    }
+}
+
+/// Poll a future that completes without ever yielding
+fn block_on_ready<F: Future>(fut: F) -> F::Output {
+    let mut fut = core::pin::pin!(fut);
+    let mut cx = Context::from_waker(Waker::noop());
+    match fut.as_mut().poll(&mut cx) {
+        Poll::Ready(out) => out,
+        Poll::Pending => panic!("future was not immediately ready"),
+    }
+}
+
 fn main() {
     // Access metadata using fn_shape! macro
     println!("add shape: {:?}", fn_shape!(add));
@@ -32,6 +216,29 @@ fn main() {
     );
     println!("generic_add<i32> shape: {:?}", fn_shape!(generic_add<i32>));
     println!("generic_add<i64> shape: {:?}", fn_shape!(generic_add<i64>));
+    println!("sum_where<u8> shape: {:?}", fn_shape!(sum_where<u8>));
+    println!("first_word shape: {:?}", fn_shape!(first_word));
+    println!("str_len shape: {:?}", fn_shape!(str_len));
+    println!("last_item<u8> shape: {:?}", fn_shape!(last_item<u8>));
+    println!("checksum<4> shape: {:?}", fn_shape!(checksum<4>));
+    println!("lookup shape: {:?}", fn_shape!(lookup));
+    println!("apply_twice shape: {:?}", fn_shape!(apply_twice));
+    println!(
+        "first_item shape: {:?}",
+        fn_shape!(first_item<std::vec::IntoIter<u8>>)
+    );
+    println!("countdown shape: {:?}", fn_shape!(countdown));
+    println!("double shape: {:?}", fn_shape!(double));
+    println!("answer shape: {:?}", fn_shape!(answer));
+    println!("read_first shape: {:?}", fn_shape!(read_first));
+    println!("ignore_input shape: {:?}", fn_shape!(ignore_input));
+    println!("pad shape: {:?}", fn_shape!(pad));
+    println!("Counter::new shape: {:?}", fn_shape!(Counter::new));
+    println!("Counter::add shape: {:?}", fn_shape!(Counter::add));
+    println!(
+        "Wrapper::<u8>::convert<u16> shape: {:?}",
+        fn_shape!(Wrapper::<u8>::convert<u16>)
+    );
 
     // Call functions normally
     println!("add(2, 3) = {}", add(2, 3));
@@ -40,11 +247,60 @@ fn main() {
     println!("generic_add<usize>(2,3) = {}", generic_add::<usize>(2, 3));
     println!("generic_add<i32>(2,3) = {}", generic_add::<i32>(2, 3));
     println!("generic_add<i64>(2,3) = {}", generic_add::<i64>(2, 3));
+    println!("sum_where<u8>(2,3) = {}", sum_where::<u8>(2, 3));
+    println!(
+        r#"first_word("hello world") = {}"#,
+        first_word("hello world")
+    );
+    println!(r#"str_len("hello") = {}"#, str_len("hello"));
+    println!("last_item(&[1, 2]) = {:?}", last_item(&[1, 2]));
+    println!("checksum([1, 2, 3, 4]) = {}", checksum([1, 2, 3, 4]));
+    println!(
+        "lookup(..) = {:?}",
+        lookup(HashMap::from([("a".to_string(), 1)]), Ok("a".to_string()))
+    );
+    println!("apply_twice(+, 2) = {}", apply_twice(|a, b| a + b, 2));
+    println!(
+        "first_item([7], 0) = {}",
+        first_item(vec![7u8].into_iter(), 0)
+    );
+    println!("countdown(6, _, (2, 0)) = {:?}", countdown(6, true, (2, 0)));
+    println!("double(21) = {}", double(21));
+    println!("ignore_input(7) = {:?}", ignore_input(7));
+    println!(r#"pad("ab", 4, None) = {}"#, pad("ab", 4, None));
+    println!("answer() = {}", block_on_ready(answer()));
+    println!("read_first(b\"x\") = {}", unsafe {
+        read_first(b"x".as_ptr())
+    });
+    let mut counter = Counter::new();
+    println!("counter.add(2) = {}", counter.add(2));
+    println!("counter.get() = {}", counter.get());
+    println!(
+        "Box::new(counter).boxed_get() = {}",
+        Box::new(counter).boxed_get()
+    );
+    println!(
+        "Counter::new().into_inner() = {}",
+        Counter::new().into_inner()
+    );
+    let wrapper = Wrapper(7u8);
+    println!("wrapper.convert::<u16>() = {}", wrapper.convert::<u16>());
+    println!("wrapper.unshaped() = {}", wrapper.unshaped());
+    println!("wrapper.inner() = {}", wrapper.inner());
+    println!("wrapper.pair(&'x').1 = {}", wrapper.pair(&'x').1);
+    let mut thermostat = Thermostat::default();
+    thermostat.set_target(20.0);
+    println!(
+        "thermostat.target_in(true) = {}",
+        thermostat.target_in(true)
+    );
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use fn_shape::{CallError, FunctionShape, ParamKind, Receiver, ReturnKind};
+    use std::any::Any;
 
     #[test]
     fn test_add_functionality() {
@@ -106,4 +362,472 @@ mod tests {
         assert_eq!(shape_i64.param_count, 2);
         assert_eq!(shape_i64.param_names, vec!["x", "y"]);
     }
+
+    #[test]
+    fn test_generic_add_signature() {
+        let shape_i32 = fn_shape!(generic_add<i32>);
+        let shape_i64 = fn_shape!(generic_add<i64>);
+
+        // Render the declared and the instantiated signature
+        assert_eq!(
+            shape_i32.to_string(),
+            "fn generic_add<T: Add<Output = T>>(x: T, y: T) -> T"
+        );
+        assert_eq!(
+            format!("{shape_i32:#}"),
+            "generic_add::<i32>(x: i32, y: i32) -> i32"
+        );
+        assert_ne!(shape_i32.generic_args, shape_i64.generic_args);
+    }
+
+    #[test]
+    fn test_qualifiers_preserved() {
+        // 1) Verify the wrappers keep their qualifiers and still work
+        const DOUBLED: u32 = double(4);
+        assert_eq!(DOUBLED, 8);
+        assert_eq!(block_on_ready(answer()), 42);
+        assert_eq!(unsafe { read_first(b"z".as_ptr()) }, b'z');
+
+        // 2) Check the recorded qualifiers on each shape
+        let shape = fn_shape!(add);
+        assert_eq!(shape.qualifiers.visibility, "");
+        assert!(!shape.qualifiers.is_const);
+
+        let shape = fn_shape!(double);
+        assert_eq!(shape.qualifiers.visibility, "pub(crate)");
+        assert!(shape.qualifiers.is_const);
+
+        let shape = fn_shape!(answer);
+        assert!(shape.qualifiers.is_async);
+        assert!(!shape.qualifiers.is_unsafe);
+
+        let shape = fn_shape!(read_first);
+        assert!(shape.qualifiers.is_unsafe);
+        assert_eq!(shape.qualifiers.abi, Some("C"));
+    }
+
+    #[test]
+    fn test_attributes_forwarded() {
+        // 1) Doc comments are captured on the shape
+        let shape = fn_shape!(add);
+        assert_eq!(
+            shape.doc,
+            "Adds two numbers.\n\nOverflow wraps in release builds."
+        );
+        assert_eq!(fn_shape!(greet).doc, "");
+
+        // 2) A cfg-gated function exists with all its generated items
+        assert!(only_in_tests());
+        assert_eq!(fn_shape!(only_in_tests).name, "only_in_tests");
+    }
+
+    #[test]
+    fn test_where_clause_functionality() {
+        // 1) Verify that sum_where works with its where clause intact
+        assert_eq!(sum_where(1.5f64, 2.0), 3.5);
+
+        // 2) Check the recorded where predicates
+        let shape = fn_shape!(sum_where<f64>);
+        assert_eq!(shape.name, "sum_where");
+        assert_eq!(
+            shape.where_predicates,
+            vec!["T : Add < Output = T > + Copy"]
+        );
+        assert!(fn_shape!(add).where_predicates.is_empty());
+    }
+
+    #[test]
+    fn test_lifetime_functionality() {
+        // 1) Verify that borrowing functions work through the wrapper
+        assert_eq!(first_word("hello world"), "hello");
+        assert_eq!(str_len("four"), 4);
+        assert_eq!(last_item(&["a", "b"]), Some(&"b"));
+
+        // 2) Check the shapes, including declared lifetimes
+        let shape = fn_shape!(first_word);
+        assert_eq!(shape.name, "first_word");
+        assert_eq!(shape.lifetimes, vec!["'a"]);
+
+        let shape = fn_shape!(str_len);
+        assert_eq!(shape.param_names, vec!["text"]);
+        assert!(shape.lifetimes.is_empty());
+
+        let shape = fn_shape!(last_item<i32>);
+        assert_eq!(shape.param_count, 1);
+    }
+
+    #[test]
+    fn test_const_generic_functionality() {
+        // 1) Verify that checksum works for different array lengths
+        assert_eq!(checksum([1, 2, 3, 4]), 10);
+        assert_eq!(checksum::<2>([5, 5]), 10);
+
+        // 2) Check the shape lists the const parameter
+        let shape = fn_shape!(checksum<4>);
+        assert_eq!(shape.name, "checksum");
+        assert_eq!(shape.param_names, vec!["bytes"]);
+        assert_eq!(shape.const_params.len(), 1);
+        assert_eq!(shape.const_params[0].name, "N");
+        assert_eq!(shape.const_params[0].type_name, "usize");
+        assert!(fn_shape!(add).const_params.is_empty());
+    }
+
+    #[test]
+    fn test_angle_bracket_param_types() {
+        // 1) Verify that parameters with commas inside generics are kept intact
+        let scores = HashMap::from([("a".to_string(), 1), ("b".to_string(), 2)]);
+        assert_eq!(lookup(scores, Ok("b".to_string())), Some(2));
+        assert_eq!(apply_twice(|a, b| a * b, 3), 27);
+        assert_eq!(first_item(Vec::<u8>::new().into_iter(), 9), 9);
+
+        // 2) Check the shapes see the right number of parameters
+        let shape = fn_shape!(lookup);
+        assert_eq!(shape.param_count, 2);
+        assert_eq!(shape.param_names, vec!["scores", "key"]);
+
+        let shape = fn_shape!(apply_twice);
+        assert_eq!(shape.param_names, vec!["f", "x"]);
+
+        let shape = fn_shape!(first_item<std::vec::IntoIter<u8>>);
+        assert_eq!(shape.param_names, vec!["iter", "fallback"]);
+    }
+
+    #[test]
+    fn test_pattern_parameters() {
+        // 1) Verify that mut bindings, wildcards and tuple patterns work
+        assert_eq!(countdown(10, false, (3, 2)), vec![10, 7, 4]);
+
+        // 2) Check the display names and pattern flags
+        let shape = fn_shape!(countdown);
+        assert_eq!(shape.param_count, 3);
+        assert_eq!(shape.param_names, vec!["from", "_1", "_2"]);
+        let is_pattern: Vec<_> = shape.params.iter().map(|p| p.is_pattern).collect();
+        assert_eq!(is_pattern, vec![false, true, true]);
+        assert_eq!(
+            shape.to_string(),
+            "fn countdown(from: u32, _: bool, (step, floor): (u32, u32)) -> Vec<u32>"
+        );
+        assert!(fn_shape!(add).params.iter().all(|p| !p.is_pattern));
+    }
+
+    #[test]
+    fn test_method_shapes() {
+        // 1) Verify that the methods work unchanged
+        let mut counter = Counter::new();
+        assert_eq!(counter.add(3), 3);
+        assert_eq!(counter.get(), 3);
+        assert_eq!(Box::new(counter).boxed_get(), 3);
+        assert_eq!(Wrapper(5u8).convert::<u32>(), 5);
+
+        // 2) Check the receivers and Self type
+        let shape = fn_shape!(Counter::new);
+        assert_eq!(shape.name, "new");
+        assert!(shape.receiver.is_none());
+        assert_eq!(shape.self_type, Some("Counter"));
+        assert_eq!(shape.doc, "Creates a counter at zero.");
+
+        let shape = fn_shape!(Counter::add);
+        assert_eq!(shape.param_names, vec!["n"]);
+        assert_eq!(shape.receiver, Some(Receiver::RefMut));
+        assert_eq!(fn_shape!(Counter::get).receiver, Some(Receiver::Ref));
+        assert_eq!(
+            fn_shape!(Counter::into_inner).receiver,
+            Some(Receiver::Value)
+        );
+        assert_eq!(
+            fn_shape!(Counter::boxed_get).receiver,
+            Some(Receiver::Typed("Box < Self >"))
+        );
+
+        // 3) Only the marked method of a generic impl is shaped
+        let shape = fn_shape!(Wrapper::<u8>::convert<u16>);
+        assert_eq!(shape.param_count, 0);
+        assert_eq!(shape.receiver, Some(Receiver::Ref));
+        assert_eq!(shape.self_type, Some("Wrapper < T >"));
+        let shape = fn_shape!(Wrapper::<u8>::pair<char>);
+        assert_eq!(shape.lifetimes, ["'a"]);
+        assert_eq!(shape.ret.type_name, "(& 'a Self, & 'a V)");
+        assert!(fn_shape!(add).receiver.is_none());
+        assert!(fn_shape!(add).self_type.is_none());
+    }
+
+    fn describe(shape: &FunctionShape) -> String {
+        format!("{}({})", shape.name, shape.param_names.join(", "))
+    }
+
+    #[test]
+    fn test_shapes_are_one_type() {
+        // Shapes of unrelated functions and methods fit in one Vec
+        let shapes: Vec<FunctionShape> = vec![
+            fn_shape!(add),
+            fn_shape!(greet),
+            fn_shape!(generic_add<u8>),
+            fn_shape!(countdown),
+            fn_shape!(Counter::add),
+        ];
+        let described: Vec<_> = shapes.iter().map(describe).collect();
+        assert_eq!(
+            described,
+            vec![
+                "add(x, y)",
+                "greet(name)",
+                "generic_add(x, y)",
+                "countdown(from, _1, _2)",
+                "add(n)",
+            ]
+        );
+        assert_eq!(shapes[0], fn_shape!(add));
+        assert_ne!(shapes[0], shapes[4]);
+        assert_eq!(fn_shape!(generic_add<u8>).type_params, vec!["T"]);
+    }
+
+    // Shapes are constants, so tables of them can be built at compile time
+    static SHAPES: [FunctionShape; 6] = [
+        fn_shape!(add),
+        fn_shape!(first_word),
+        fn_shape!(generic_add<u8>),
+        fn_shape!(checksum<4>),
+        fn_shape!(Wrapper::<u8>::convert<u16>),
+        fn_shape!(Wrapper::<u8>::inner),
+    ];
+    const ADD_ARITY: usize = fn_shape!(add).param_count;
+    const _: () = assert!(fn_shape!(Counter::add).param_count == 1);
+
+    #[test]
+    fn test_const_shapes() {
+        let names: Vec<_> = SHAPES.iter().map(|shape| shape.name).collect();
+        assert_eq!(
+            names,
+            vec![
+                "add",
+                "first_word",
+                "generic_add",
+                "checksum",
+                "convert",
+                "inner"
+            ]
+        );
+        assert_eq!(ADD_ARITY, 2);
+        assert_eq!(SHAPES[0], ADD_SHAPE);
+        assert_eq!(SHAPES[1].lifetimes, vec!["'a"]);
+        assert_eq!(SHAPES[3].const_params, fn_shape!(checksum<4>).const_params);
+        assert_eq!(SHAPES[2], GENERIC_ADD_SHAPE::<u8>::SHAPE);
+        assert_ne!(SHAPES[2], fn_shape!(generic_add<i8>));
+        assert_eq!(SHAPES[5].ret.rust_type_name(), Some("u8"));
+        assert_eq!(Wrapper(3u8).inner(), 3);
+    }
+
+    #[test]
+    fn test_param_shapes() {
+        // 1) Source and resolved type names, including generic instantiations
+        let shape = fn_shape!(first_item<std::vec::IntoIter<u8>>);
+        assert_eq!(shape.params[0].index, 0);
+        assert_eq!(shape.params[1].index, 1);
+        assert_eq!(shape.params[1].type_name, "< I as Iterator > :: Item");
+        assert_eq!(shape.params[1].rust_type_name(), Some("u8"));
+
+        let shape = fn_shape!(checksum<4>);
+        assert_eq!(shape.params[0].rust_type_name(), Some("[u8; 4]"));
+
+        // 2) Reference kinds, and no resolved type for `impl Trait`
+        let shape = fn_shape!(first_word);
+        assert_eq!(shape.params[0].kind, ParamKind::Ref);
+        assert_eq!(shape.params[0].rust_type_name(), Some("&str"));
+
+        let shape = fn_shape!(apply_twice);
+        assert_eq!(shape.params[0].kind, ParamKind::ImplTrait);
+        assert_eq!(shape.params[0].rust_type_name(), None);
+        assert_eq!(shape.params[1].kind, ParamKind::Owned);
+
+        // 3) Pattern parameters keep their type
+        let shape = fn_shape!(countdown);
+        assert_eq!(shape.params[2].type_name, "(u32, u32)");
+        assert_eq!(shape.params[2].rust_type_name(), Some("(u32, u32)"));
+    }
+
+    #[test]
+    fn test_return_shapes() {
+        // 1) Option returns expose the inner type, resolved per instantiation
+        let ret = fn_shape!(last_item<u8>).ret;
+        assert_eq!(ret.type_name, "Option < & T >");
+        let ReturnKind::Option { some } = ret.kind else {
+            panic!("expected an Option return, got {:?}", ret.kind);
+        };
+        assert_eq!(some.type_name, "& T");
+        assert_eq!(some.rust_type.map(|ty| ty.get()), Some("&u8"));
+        assert!(!ret.is_fallible());
+
+        let ret = fn_shape!(lookup).ret;
+        assert!(matches!(ret.kind, ReturnKind::Option { some } if some.type_name == "i32"));
+
+        // 2) Other returns are plain, and a missing return type is unit
+        let ret = fn_shape!(first_item<std::vec::IntoIter<u8>>).ret;
+        assert_eq!(ret.kind, ReturnKind::Plain);
+        assert_eq!(ret.rust_type_name(), Some("u8"));
+        let (add, ignore_input) = (fn_shape!(Counter::add), fn_shape!(ignore_input));
+        assert!(!add.ret.is_unit);
+        assert!(ignore_input.ret.is_unit);
+    }
+
+    #[test]
+    fn test_call_dyn() {
+        // 1) Free functions, with references passed as `'static` values
+        let mut args: Vec<Box<dyn Any>> = vec![Box::new("Ada".to_string())];
+        let out = fn_shape!(greet).call_dyn(&mut args).unwrap();
+        assert_eq!(out.downcast_ref::<String>().unwrap(), "Hello, Ada!");
+
+        let mut args: Vec<Box<dyn Any>> = vec![Box::new("rust is fun")];
+        let out = fn_shape!(str_len).call_dyn(&mut args).unwrap();
+        assert_eq!(out.downcast_ref::<usize>(), Some(&11));
+
+        // Lifetime parameters become `'static`
+        let mut args: Vec<Box<dyn Any>> = vec![Box::new("rust is fun")];
+        let out = fn_shape!(first_word).call_dyn(&mut args).unwrap();
+        assert_eq!(out.downcast_ref::<&str>(), Some(&"rust"));
+
+        // 2) Methods take their receiver first; `&mut self` borrows it in place
+        let mut args: Vec<Box<dyn Any>> = vec![];
+        let counter = fn_shape!(Counter::new).call_dyn(&mut args).unwrap();
+        let mut args: Vec<Box<dyn Any>> = vec![counter, Box::new(5u32)];
+        let out = fn_shape!(Counter::add).call_dyn(&mut args).unwrap();
+        assert_eq!(out.downcast_ref::<u32>(), Some(&5));
+        let mut args: Vec<Box<dyn Any>> = vec![args.remove(0)];
+        let out = fn_shape!(Counter::into_inner).call_dyn(&mut args).unwrap();
+        assert_eq!(out.downcast_ref::<u32>(), Some(&5));
+
+        let mut args: Vec<Box<dyn Any>> = vec![Box::new(Box::new(Counter(7)))];
+        let out = fn_shape!(Counter::boxed_get).call_dyn(&mut args).unwrap();
+        assert_eq!(out.downcast_ref::<u32>(), Some(&7));
+
+        // 3) Generic, async and unsafe functions are not callable
+        for shape in [
+            fn_shape!(last_item<u8>),
+            fn_shape!(answer),
+            fn_shape!(read_first),
+            fn_shape!(Wrapper::<u8>::convert<u16>),
+        ] {
+            assert_eq!(
+                shape.call_dyn(&mut []).err(),
+                Some(CallError::NotCallable { name: shape.name })
+            );
+        }
+    }
+
+    #[test]
+    fn test_registry() {
+        // Shapes from this crate are found by name, including cfg-gated ones
+        let shape = fn_shape::registry::get("greet").expect("greet is registered");
+        assert_eq!(shape, fn_shape!(greet));
+        assert!(fn_shape::registry::get("only_in_tests").is_some());
+        assert!(fn_shape::registry::get("Counter::into_inner").is_some());
+
+        // Generic functions and methods of generic impls have no single shape to register, but
+        // functions with only lifetime parameters do
+        assert!(fn_shape::registry::get("generic_add").is_none());
+        assert!(fn_shape::registry::get("first_word").is_some());
+        let all = fn_shape::registry::all();
+        assert!(all
+            .iter()
+            .all(|shape| shape.self_type != Some("Wrapper < T >")));
+        assert!(all
+            .windows(2)
+            .all(|w| w[0].qualified_name() <= w[1].qualified_name()));
+        assert!(only_in_tests());
+
+        // Methods are registered under their exported name
+        let shape = fn_shape::registry::get("Thermostat::setTarget").expect("should be registered");
+        assert_eq!(shape, fn_shape!(Thermostat::set_target));
+        assert!(fn_shape::registry::tagged("climate").contains(&shape));
+    }
+
+    #[test]
+    fn test_attribute_arguments() {
+        // The impl block's arguments apply to each method unless its marker overrides them
+        let shape = fn_shape!(Thermostat::set_target);
+        assert_eq!((shape.name, shape.ident), ("setTarget", "set_target"));
+        assert_eq!(shape.qualified_name(), "Thermostat::setTarget");
+        assert_eq!(shape.tags, &["climate"]);
+        assert_eq!(shape.param_names, &["targetCelsius"]);
+
+        let shape = fn_shape!(Thermostat::target_in);
+        assert_eq!(shape.name, "target_in");
+        assert!(shape.tags.is_empty());
+        assert_eq!(shape.param_names, &["in_fahrenheit"]);
+
+        let mut thermostat = Thermostat::default();
+        thermostat.set_target(100.0);
+        assert_eq!(thermostat.target_in(true), 212.0);
+    }
+
+    #[test]
+    fn test_named_args() {
+        assert_eq!(fn_shape::call!(add, y = 3, x = 2), 5);
+        let name = "Ada".to_string();
+        assert_eq!(fn_shape::call!(greet, name), "Hello, Ada!");
+        assert_eq!(fn_shape::call!(no_params), "No parameters here!");
+
+        // Elided lifetimes become the struct's lifetime
+        let text = String::from("four");
+        let args = StrLenArgs { text: &text };
+        assert_eq!(args.call(), 4);
+
+        // Pattern parameters are named by position
+        let args = CountdownArgs {
+            from: 5,
+            __arg1: true,
+            __arg2: (2, 1),
+        };
+        assert_eq!(args.call(), vec![5, 3, 1]);
+
+        let args = AddArgs::builder()
+            .with_y(1)
+            .with_x(2)
+            .build()
+            .expect("should build");
+        assert_eq!((args.x, args.y), (2, 1));
+        assert_eq!(args.call(), 3);
+        assert_eq!(
+            AddArgs::builder().with_x(2).build().err(),
+            Some(fn_shape::MissingArg {
+                function: "add",
+                name: "y"
+            })
+        );
+
+        // Qualifiers carry over to `call`
+        assert_eq!(block_on_ready(AnswerArgs {}.call()), 42);
+        let byte = 7u8;
+        assert_eq!(unsafe { fn_shape::call!(read_first, ptr = &byte) }, 7);
+        assert!(fn_shape::call!(only_in_tests));
+    }
+
+    #[test]
+    fn test_param_defaults() {
+        // Parameters with a default may be left out of named calls
+        assert_eq!(fn_shape::call!(pad, text = "ab"), "ab....");
+        assert_eq!(
+            fn_shape::call!(pad, fill = Some('-'), text = "ab"),
+            "ab----"
+        );
+        assert_eq!(fn_shape::call!(pad, text = "ab", width = 3), "ab.");
+        let args = PadArgs::builder()
+            .with_text("abc")
+            .build()
+            .expect("should build");
+        assert_eq!((args.width, args.fill), (6, None));
+        assert_eq!(pad("ab", 0, Some('x')), "ab");
+
+        // The shape records the defaults as written
+        let defaults: Vec<_> = fn_shape!(pad).params.iter().map(|p| p.default).collect();
+        assert_eq!(defaults, vec![None, Some("6"), Some("Default::default()")]);
+
+        // Method parameters take defaults too, and the attribute is stripped from the method
+        let shape = fn_shape!(Counter::add);
+        assert_eq!(shape.params[0].default, Some("1"));
+        let mut counter = Counter::new();
+        assert_eq!(counter.add(2), 2);
+        let mut args: Vec<Box<dyn Any>> = vec![Box::new(counter), Box::new(4u32)];
+        let total = shape.call_dyn(&mut args).expect("should call");
+        assert_eq!(total.downcast_ref::<u32>(), Some(&6));
+    }
 }